@@ -0,0 +1,170 @@
+//! Benchmarks of calendar field access and construction
+//!
+//! Builtin calendars are dispatched statically through `Calendar`. For comparison, the
+//! `boxed` cases go through a `Box<dyn CalendarProtocol>` and allocate the month code like
+//! the `String` returning API did before, and the `custom` benchmark goes through the
+//! registry and a trait object.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use temporal_core::{Calendar, CalendarProtocol, IsoDate, PlainDate};
+
+const CALENDARS: [Calendar; 5] = [
+    Calendar::Iso8601,
+    Calendar::Chinese,
+    Calendar::Coptic,
+    Calendar::Ethiopic,
+    Calendar::Indian,
+];
+
+fn dates() -> Vec<IsoDate> {
+    (1..=12)
+        .flat_map(|m| (1..=28).map(move |d| IsoDate::new(2023, m, d).unwrap()))
+        .collect()
+}
+
+fn fields(c: &mut Criterion) {
+    let dates = dates();
+    let mut group = c.benchmark_group("fields");
+    for calendar in CALENDARS {
+        group.bench_function(calendar.id(), |b| {
+            b.iter(|| {
+                for &date in &dates {
+                    black_box(calendar.year(date));
+                    black_box(calendar.month_code(date));
+                    black_box(calendar.day(date));
+                }
+            })
+        });
+        let boxed: Box<dyn CalendarProtocol> = Box::new(calendar);
+        group.bench_function(format!("{}/boxed", calendar.id()), |b| {
+            b.iter(|| {
+                for &date in &dates {
+                    black_box(boxed.year(date));
+                    black_box(boxed.month_code(date).to_string());
+                    black_box(boxed.day(date));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn id(c: &mut Criterion) {
+    let mut group = c.benchmark_group("id");
+    for calendar in CALENDARS {
+        group.bench_function(calendar.id(), |b| {
+            b.iter(|| black_box(black_box(calendar).id().len()))
+        });
+        let boxed: Box<dyn CalendarProtocol> = Box::new(calendar);
+        group.bench_function(format!("{}/boxed", calendar.id()), |b| {
+            b.iter(|| black_box(black_box(&boxed).id().to_string().len()))
+        });
+    }
+    group.finish();
+}
+
+fn era(c: &mut Criterion) {
+    let dates = dates();
+    let mut group = c.benchmark_group("era");
+    for calendar in [Calendar::Chinese, Calendar::Coptic] {
+        group.bench_function(calendar.id(), |b| {
+            b.iter(|| {
+                for &date in &dates {
+                    black_box(calendar.era(date));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn from_ymd(c: &mut Criterion) {
+    let mut group = c.benchmark_group("from_ymd");
+    for calendar in CALENDARS {
+        group.bench_function(calendar.id(), |b| {
+            b.iter(|| {
+                for month in 1..=12 {
+                    for day in 1..=28 {
+                        black_box(PlainDate::from_ymd(2000, month, day, calendar).constrain());
+                    }
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+/// ISO calendar wrapped as a custom calendar
+struct Custom;
+
+impl CalendarProtocol for Custom {
+    fn id(&self) -> &str {
+        "bench-custom"
+    }
+    fn era(&self, iso_date: IsoDate) -> Option<temporal_core::Era> {
+        Calendar::Iso8601.era(iso_date)
+    }
+    fn year(&self, iso_date: IsoDate) -> i32 {
+        Calendar::Iso8601.year(iso_date)
+    }
+    fn month(&self, iso_date: IsoDate) -> u32 {
+        Calendar::Iso8601.month(iso_date)
+    }
+    fn month_code(&self, iso_date: IsoDate) -> temporal_core::MonthCode {
+        Calendar::Iso8601.month_code(iso_date)
+    }
+    fn day(&self, iso_date: IsoDate) -> u32 {
+        Calendar::Iso8601.day(iso_date)
+    }
+    fn day_of_week(&self, iso_date: IsoDate) -> u32 {
+        Calendar::Iso8601.day_of_week(iso_date)
+    }
+    fn day_of_year(&self, iso_date: IsoDate) -> u32 {
+        Calendar::Iso8601.day_of_year(iso_date)
+    }
+    fn week_of_year(&self, iso_date: IsoDate) -> u32 {
+        Calendar::Iso8601.week_of_year(iso_date)
+    }
+    fn days_in_week(&self, iso_date: IsoDate) -> u32 {
+        Calendar::Iso8601.days_in_week(iso_date)
+    }
+    fn days_in_month(&self, iso_date: IsoDate) -> u32 {
+        Calendar::Iso8601.days_in_month(iso_date)
+    }
+    fn days_in_year(&self, iso_date: IsoDate) -> u32 {
+        Calendar::Iso8601.days_in_year(iso_date)
+    }
+    fn months_in_year(&self, iso_date: IsoDate) -> u32 {
+        Calendar::Iso8601.months_in_year(iso_date)
+    }
+    fn in_leap_year(&self, iso_date: IsoDate) -> bool {
+        Calendar::Iso8601.in_leap_year(iso_date)
+    }
+    fn from_ymd(&self, year: i32, month: u32, day: u32) -> temporal_core::FromYMDResult {
+        Calendar::Iso8601.from_ymd(year, month, day)
+    }
+    fn date_add(
+        &self,
+        iso_date: IsoDate,
+        dur: temporal_core::NominalDuration,
+    ) -> temporal_core::FromYMDResult {
+        Calendar::Iso8601.date_add(iso_date, dur)
+    }
+}
+
+fn custom(c: &mut Criterion) {
+    let calendar = Calendar::register(Custom).unwrap();
+    let dates = dates();
+    c.bench_function("fields/custom", |b| {
+        b.iter(|| {
+            for &date in &dates {
+                black_box(calendar.year(date));
+                black_box(calendar.month_code(date));
+                black_box(calendar.day(date));
+            }
+        })
+    });
+}
+
+criterion_group!(benches, fields, id, era, from_ymd, custom);
+criterion_main!(benches);
@@ -0,0 +1,45 @@
+//! Benchmarks of offset and transition lookups in builtin time zones
+//!
+//! The instants are in 2024, in the middle of the transition table, and in 2099, near its
+//! end where a zone with daylight saving time has the most transitions to skip.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use temporal_core::{SignedDuration, TimeZone, TimeZoneProtocol};
+
+const ZONES: [&str; 3] = ["America/New_York", "Europe/Berlin", "Asia/Tokyo"];
+
+/// July 1st of 2024 and 2099 at midnight UTC
+const INSTANTS: [(i32, i64); 2] = [(2024, 1_719_792_000), (2099, 4_086_547_200)];
+
+fn offset(c: &mut Criterion) {
+    let mut group = c.benchmark_group("offset");
+    for id in ZONES {
+        let tz: TimeZone = id.parse().unwrap();
+        for (year, sec) in INSTANTS {
+            group.bench_function(format!("{}/{}", id, year), |b| {
+                b.iter(|| black_box(tz.get_second_offset(black_box(sec))))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn transition(c: &mut Criterion) {
+    let mut group = c.benchmark_group("transition");
+    for id in ZONES {
+        let tz: TimeZone = id.parse().unwrap();
+        for (year, sec) in INSTANTS {
+            let epoch = SignedDuration::from_secs(sec);
+            group.bench_function(format!("{}/{}/next", id, year), |b| {
+                b.iter(|| black_box(tz.next_transition(black_box(epoch))))
+            });
+            group.bench_function(format!("{}/{}/previous", id, year), |b| {
+                b.iter(|| black_box(tz.previous_transition(black_box(epoch))))
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, offset, transition);
+criterion_main!(benches);
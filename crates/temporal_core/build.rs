@@ -3,17 +3,17 @@
 #[cfg(feature = "filter-by-regex")]
 extern crate regex;
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::env;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader, Write};
 use std::path::Path;
 
-use parse_zoneinfo::line::{Line, LineParser};
+use parse_zoneinfo::line::{DaySpec, Line, LineParser, TimeType, Year};
 use parse_zoneinfo::structure::{Child, Structure};
-use parse_zoneinfo::table::{Table, TableBuilder};
-use parse_zoneinfo::transitions::FixedTimespan;
+use parse_zoneinfo::table::{RuleInfo, Saving, Table, TableBuilder};
 use parse_zoneinfo::transitions::TableTransitions;
+use parse_zoneinfo::transitions::{FixedTimespan, FixedTimespanSet};
 
 /// The name of the environment variable which possibly holds the filter regex.
 const FILTER_ENV_VAR_NAME: &str = "CHRONO_TZ_TIMEZONE_FILTER";
@@ -29,31 +29,229 @@ fn strip_comments(mut line: String) -> String {
     line
 }
 
-// Generate a list of the time zone periods beyond the first that apply
-// to this zone, as a string representation of a static slice.
-fn format_rest(rest: Vec<(i64, FixedTimespan)>) -> String {
-    let mut ret = "&[\n".to_string();
-    for (
+// Number of transitions of a zone between two checkpoints, which give the full time of
+// a transition so that the others only need the seconds since the last checkpoint.
+const CHECKPOINT_INTERVAL: usize = 16;
+
+// Offsets and abbreviation of a timespan, which identify it in the pool
+type TimespanKey = (i64, i64, String);
+
+fn timespan_key(span: &FixedTimespan) -> TimespanKey {
+    let name = posix_name(&span.name, span.utc_offset + span.dst_offset);
+    (span.utc_offset, span.dst_offset, name)
+}
+
+// The timespans of all zones, deduplicated, with the most used first.
+struct TimespanPool {
+    spans: Vec<TimespanKey>,
+    indices: HashMap<TimespanKey, usize>,
+}
+
+impl TimespanPool {
+    fn new<'a>(sets: impl Iterator<Item = &'a FixedTimespanSet>) -> TimespanPool {
+        let mut counts = HashMap::new();
+        for set in sets {
+            for span in std::iter::once(&set.first).chain(set.rest.iter().map(|x| &x.1)) {
+                *counts.entry(timespan_key(span)).or_insert(0) += 1;
+            }
+        }
+        let mut counts = counts.into_iter().collect::<Vec<_>>();
+        counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+        let spans = counts.into_iter().map(|(span, _)| span).collect::<Vec<_>>();
+        let indices = spans.iter().cloned().zip(0..).collect();
+        TimespanPool { spans, indices }
+    }
+
+    fn index(&self, span: &FixedTimespan) -> usize {
+        self.indices[&timespan_key(span)]
+    }
+
+    // Encode the transitions after the first timespan as the times of every
+    // `CHECKPOINT_INTERVAL`th one, the seconds since the last of those of each one, and the
+    // indices of their timespans.
+    //
+    // A transition too far from the last checkpoint for a `u32` starts a new one, after
+    // transitions to the same timespan which fill the interval. Lookups skip those like
+    // the ones between zone lines with the same offsets.
+    fn encode(&self, rest: &[(i64, FixedTimespan)]) -> (Vec<i64>, Vec<u32>, Vec<u16>) {
+        let mut transitions: Vec<(i64, usize)> = vec![];
+        for (start, span) in rest {
+            loop {
+                let checkpoint = transitions.len() / CHECKPOINT_INTERVAL * CHECKPOINT_INTERVAL;
+                if checkpoint == transitions.len()
+                    || start - transitions[checkpoint].0 <= u32::MAX as i64
+                {
+                    break;
+                }
+                let previous = transitions[transitions.len() - 1].1;
+                transitions.push((transitions[checkpoint].0 + u32::MAX as i64, previous));
+            }
+            transitions.push((*start, self.index(span)));
+        }
+        let checkpoints = transitions
+            .iter()
+            .step_by(CHECKPOINT_INTERVAL)
+            .map(|(start, _)| *start)
+            .collect::<Vec<_>>();
+        let offsets = transitions
+            .iter()
+            .enumerate()
+            .map(|(i, (start, _))| (start - checkpoints[i / CHECKPOINT_INTERVAL]) as u32)
+            .collect();
+        let spans = transitions
+            .iter()
+            .map(|(_, index)| u16::try_from(*index).expect("too many timespans"))
+            .collect();
+        (checkpoints, offsets, spans)
+    }
+}
+
+// The abbreviation of a timespan with the given offset east of UTC, where the `%z` format
+// gives a numeric abbreviation like `+0530`.
+fn posix_name(name: &str, offset: i64) -> String {
+    if !name.contains("%z") {
+        return name.to_string();
+    }
+    let sign = if offset < 0 { '-' } else { '+' };
+    let (hours, minutes) = (offset.abs() / 3600, offset.abs() / 60 % 60);
+    let numeric = if minutes == 0 {
+        format!("{}{:02}", sign, hours)
+    } else {
+        format!("{}{:02}{:02}", sign, hours, minutes)
+    };
+    name.replace("%z", &numeric)
+}
+
+// Write the date and time of a rule as a `PosixTransition`, with the time counted in the
+// local time before the transition, whose daylight saving is `save_before`.
+fn posix_transition(rule: &RuleInfo, utc_offset: i64, save_before: i64) -> Option<String> {
+    const DAYS_BEFORE_MONTH: [i64; 12] = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+    let month = rule.month as i64;
+    let mut time = match rule.time_type {
+        TimeType::Wall => rule.time,
+        TimeType::Standard => rule.time + save_before,
+        TimeType::UTC => rule.time + utc_offset + save_before,
+    };
+    let month_week_day = |week: i64, weekday: i64| {
+        format!(
+            "PosixDate::MonthWeekDay {{ month: {}, week: {}, day: {} }}",
+            month, week, weekday
+        )
+    };
+    // The first `weekday` on or after `day` is the first weekday shifted back by the same
+    // number of days in the week starting at `day`, shifted forward by the time.
+    let mut first_on_or_after = |weekday: i64, day: i64| {
+        let (week, shift) = ((day - 1) / 7 + 1, (day - 1) % 7);
+        if day < 1 || week > 4 {
+            return None;
+        }
+        time += shift * 86400;
+        Some(month_week_day(week, (weekday - shift).rem_euclid(7)))
+    };
+    let date = match rule.day {
+        DaySpec::Ordinal(day) => format!(
+            "PosixDate::JulianNoLeap({})",
+            DAYS_BEFORE_MONTH[month as usize - 1] + day as i64
+        ),
+        DaySpec::Last(weekday) => month_week_day(5, weekday as i64),
+        DaySpec::FirstOnOrAfter(weekday, day) => first_on_or_after(weekday as i64, day as i64)?,
+        DaySpec::LastOnOrBefore(weekday, day) => first_on_or_after(weekday as i64, day as i64 - 6)?,
+    };
+    Some(format!(
+        "PosixTransition {{ date: {}, time: {} }}",
+        date, time
+    ))
+}
+
+// Generate the `PosixTimeZone` of the rule which applies after the last transition of
+// a zone, like `zic` does for the footer of TZif files. Zones whose rules can't be
+// described this way keep the offset of their last transition.
+fn posix_rule(table: &Table, zone: &str) -> String {
+    let info = table.get_zoneset(zone).unwrap().last().unwrap();
+    let fixed = || {
+        let timespans = table.timespans(zone).unwrap();
+        let last = timespans.rest.last().map_or(&timespans.first, |x| &x.1);
+        let offset = last.utc_offset + last.dst_offset;
+        format!(
+            "PosixTimeZone {{ std_name: {:?}, std_offset: {}, dst: None }}",
+            posix_name(&last.name, offset),
+            offset
+        )
+    };
+    let rules = match &info.saving {
+        Saving::Multiple(rules) => &table.rulesets[rules],
+        _ => return fixed(),
+    };
+    let ongoing = rules
+        .iter()
+        .filter(|r| r.to_year == Some(Year::Maximum))
+        .collect::<Vec<_>>();
+    let (dst, std) = match ongoing[..] {
+        [a, b] if a.time_to_add != 0 && b.time_to_add == 0 => (a, b),
+        [a, b] if a.time_to_add == 0 && b.time_to_add != 0 => (b, a),
+        _ => return fixed(),
+    };
+    let start = posix_transition(dst, info.offset, std.time_to_add);
+    let end = posix_transition(std, info.offset, dst.time_to_add);
+    let (start, end) = match (start, end) {
+        (Some(start), Some(end)) => (start, end),
+        _ => return fixed(),
+    };
+    let dst_offset = info.offset + dst.time_to_add;
+    format!(
+        "PosixTimeZone {{
+                    std_name: {:?},
+                    std_offset: {},
+                    dst: Some(PosixDst {{
+                        name: {:?},
+                        offset: {},
+                        start: {},
+                        end: {},
+                    }}),
+                }}",
+        posix_name(
+            &info.format.format(std.time_to_add, std.letters.as_ref()),
+            info.offset
+        ),
+        info.offset,
+        posix_name(
+            &info.format.format(dst.time_to_add, dst.letters.as_ref()),
+            dst_offset
+        ),
+        dst_offset,
         start,
-        FixedTimespan {
-            utc_offset,
-            dst_offset,
-            name,
-        },
-    ) in rest
+        end,
+    )
+}
+
+// Drop the transitions before 1970, extending the timespan in effect at 1970 back in time.
+fn drop_pre_1970(mut timespans: FixedTimespanSet) -> FixedTimespanSet {
+    let count = timespans
+        .rest
+        .iter()
+        .take_while(|(start, _)| *start <= 0)
+        .count();
+    if let Some((_, first)) = timespans.rest.drain(..count).next_back() {
+        timespans.first = first;
+    }
+    timespans
+}
+
+// Remove the links of the `backward` file, which only exist for compatibility, except
+// `UTC` which is the id of `TimeZone::UTC`.
+fn drop_backward_links(table: &mut Table, parser: &LineParser, path: &Path) {
+    let file = File::open(path).unwrap_or_else(|e| panic!("cannot open {}: {}", path.display(), e));
+    for line in BufReader::new(file)
+        .lines()
+        .map(Result::unwrap)
+        .map(strip_comments)
     {
-        ret.push_str(&format!(
-            "                    ({start}, FixedTimespan {{ \
-             utc_offset: {utc}, dst_offset: {dst}, name: \"{name}\" \
-             }}),\n",
-            start = start,
-            utc = utc_offset,
-            dst = dst_offset,
-            name = name,
-        ));
+        if let Ok(Line::Link(link)) = parser.parse_str(&line) {
+            if link.new != "UTC" {
+                table.links.remove(link.new);
+            }
+        }
     }
-    ret.push_str("                ]");
-    ret
 }
 
 // Convert all '/' to '__', all '+' to 'Plus' and '-' to 'Minus', unless
@@ -91,6 +289,10 @@ fn write_timezone_file(timezone_file: &mut File, table: &Table) -> io::Result<()
         "use core::fmt::{{self, Debug, Display, Formatter}};",
     )?;
     writeln!(timezone_file, "use core::str::FromStr;\n",)?;
+    writeln!(
+        timezone_file,
+        "use super::posix::{{PosixDate, PosixDst, PosixTimeZone, PosixTransition}};",
+    )?;
     writeln!(
         timezone_file,
         "use super::timezone_impl::{{TimeSpans, FixedTimespanSet, FixedTimespan}};\n",
@@ -128,6 +330,16 @@ fn write_timezone_file(timezone_file: &mut File, table: &Table) -> io::Result<()
         map.build()
     )?;
 
+    writeln!(
+        timezone_file,
+        "/// All zones and links, sorted by name\npub(crate) static TZ_VARIANTS: [Tz; {num}] = [",
+        num = zones.len(),
+    )?;
+    for zone in &zones {
+        writeln!(timezone_file, "    Tz::{},", convert_bad_chars(zone))?;
+    }
+    writeln!(timezone_file, "];\n")?;
+
     {
         writeln!(timezone_file, "use uncased::UncasedStr;\n",)?;
         let mut map = phf_codegen::Map::new();
@@ -186,6 +398,32 @@ impl FromStr for Tz {{
     }}"
     )?;
 
+    writeln!(
+        timezone_file,
+        "
+    /// Resolves a link, like `US/Eastern`, to the zone it refers to
+    pub(crate) fn canonical(self) -> Tz {{
+        match self {{"
+    )?;
+    for link in table.links.keys().collect::<BTreeSet<_>>() {
+        let mut target = &table.links[link];
+        while let Some(next) = table.links.get(target) {
+            target = next;
+        }
+        writeln!(
+            timezone_file,
+            "            Tz::{link} => Tz::{target},",
+            link = convert_bad_chars(link),
+            target = convert_bad_chars(target),
+        )?;
+    }
+    writeln!(
+        timezone_file,
+        "            _ => self,
+        }}
+    }}"
+    )?;
+
     {
         writeln!(
             timezone_file,
@@ -218,6 +456,38 @@ impl FromStr for Tz {{
     }}
 }}\n"
     )?;
+    let timespans = zones
+        .iter()
+        .filter(|zone| table.zonesets.contains_key(**zone))
+        .map(|zone| {
+            let timespans = table.timespans(zone).unwrap();
+            if cfg!(feature = "drop-pre-1970") {
+                (zone, drop_pre_1970(timespans))
+            } else {
+                (zone, timespans)
+            }
+        })
+        .collect::<BTreeMap<_, _>>();
+    let pool = TimespanPool::new(timespans.values());
+    writeln!(
+        timezone_file,
+        "/// Number of transitions between two checkpoints of a [`FixedTimespanSet`]
+pub(crate) const CHECKPOINT_INTERVAL: usize = {};
+
+/// Timespans of all zones, which transitions refer to by index
+pub(crate) static TIMESPANS: [FixedTimespan; {}] = [",
+        CHECKPOINT_INTERVAL,
+        pool.spans.len()
+    )?;
+    for (utc_offset, dst_offset, name) in &pool.spans {
+        writeln!(
+            timezone_file,
+            "    FixedTimespan {{ utc_offset: {}, dst_offset: {}, name: \"{}\" }},",
+            utc_offset, dst_offset, name
+        )?;
+    }
+    writeln!(timezone_file, "];\n")?;
+
     writeln!(
         timezone_file,
         "impl TimeSpans for Tz {{
@@ -225,26 +495,40 @@ impl FromStr for Tz {{
         match *self {{"
     )?;
     for zone in &zones {
-        let timespans = table.timespans(&zone).unwrap();
         let zone_name = convert_bad_chars(zone);
+        let timespans = match timespans.get(zone) {
+            Some(timespans) => timespans,
+            None => {
+                // Links share the table of the zone they refer to
+                let mut target = &table.links[*zone];
+                while let Some(next) = table.links.get(target) {
+                    target = next;
+                }
+                writeln!(
+                    timezone_file,
+                    "            Tz::{zone} => Tz::{target}.timespans(),",
+                    zone = zone_name,
+                    target = convert_bad_chars(target),
+                )?;
+                continue;
+            }
+        };
+        let (checkpoints, offsets, spans) = pool.encode(&timespans.rest);
         writeln!(
             timezone_file,
-            "            Tz::{zone} => {{
-                const REST: &[(i64, FixedTimespan)] = {rest};
-                FixedTimespanSet {{
-                    first: FixedTimespan {{
-                        utc_offset: {utc},
-                        dst_offset: {dst},
-                        name: \"{name}\",
-                    }},
-                    rest: REST
-                }}
-            }},\n",
+            "            Tz::{zone} => FixedTimespanSet {{
+                first: TIMESPANS[{first}],
+                checkpoints: &{checkpoints:?},
+                offsets: &{offsets:?},
+                spans: &{spans:?},
+                rule: {rule},
+            }},",
             zone = zone_name,
-            rest = format_rest(timespans.rest),
-            utc = timespans.first.utc_offset,
-            dst = timespans.first.dst_offset,
-            name = timespans.first.name,
+            first = pool.index(&timespans.first),
+            checkpoints = checkpoints,
+            offsets = offsets,
+            spans = spans,
+            rule = posix_rule(table, zone),
         )?;
     }
     write!(
@@ -334,6 +618,99 @@ fn write_directory_file(directory_file: &mut File, table: &Table) -> io::Result<
     Ok(())
 }
 
+// Read the rows of a tab separated file of the tz database, without comments.
+fn read_tab_file(path: &Path) -> io::Result<Vec<Vec<String>>> {
+    let mut rows = vec![];
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if !line.starts_with('#') && !line.trim().is_empty() {
+            rows.push(line.split('\t').map(str::to_string).collect());
+        }
+    }
+    Ok(rows)
+}
+
+// Convert a coordinate like `+4230` or `-0580536`, in degrees, minutes and possibly
+// seconds, to decimal degrees.
+fn parse_coordinate(s: &str) -> f64 {
+    let (sign, digits) = s.split_at(1);
+    let degree_digits = digits.len() % 2 + 2;
+    let (degrees, rest) = digits.split_at(degree_digits);
+    let mut value = degrees.parse::<f64>().unwrap();
+    let mut unit = 1.0;
+    for part in rest.as_bytes().chunks(2) {
+        unit /= 60.0;
+        value += std::str::from_utf8(part).unwrap().parse::<f64>().unwrap() * unit;
+    }
+    if sign == "-" {
+        -value
+    } else {
+        value
+    }
+}
+
+// Find the release of the tz database from the `version` file, or else from the latest
+// release in `NEWS`.
+fn database_version(tz_dir: &Path) -> Option<String> {
+    if let Ok(version) = std::fs::read_to_string(tz_dir.join("version")) {
+        return Some(version.trim().to_string());
+    }
+    let news = BufReader::new(File::open(tz_dir.join("NEWS")).ok()?);
+    news.lines().map_while(Result::ok).find_map(|line| {
+        Some(
+            line.strip_prefix("Release ")?
+                .split(' ')
+                .next()?
+                .to_string(),
+        )
+    })
+}
+
+// Append to the timezone file the locations of zones from `zone1970.tab`, or `zone.tab`
+// if it is missing, and the names of countries from `iso3166.tab`.
+fn write_location_tables(timezone_file: &mut File, table: &Table, tz_dir: &Path) -> io::Result<()> {
+    let zone_rows = read_tab_file(&tz_dir.join("zone1970.tab"))
+        .or_else(|_| read_tab_file(&tz_dir.join("zone.tab")))?;
+    let zone_rows = zone_rows
+        .iter()
+        .filter(|row| row.len() >= 3 && table.zonesets.contains_key(&row[2]))
+        .collect::<Vec<_>>();
+    writeln!(timezone_file, "use super::location::ZoneLocation;\n")?;
+    writeln!(
+        timezone_file,
+        "/// Locations of zones, in the order of `zone1970.tab`\n\
+         pub(crate) static ZONE_LOCATIONS: [(Tz, ZoneLocation); {}] = [",
+        zone_rows.len()
+    )?;
+    for row in zone_rows {
+        let coordinates = &row[1];
+        let split = coordinates[1..].find(['+', '-']).unwrap() + 1;
+        writeln!(
+            timezone_file,
+            "    (Tz::{zone}, ZoneLocation {{ country_codes: &{codes:?}, latitude: {lat:?}, \
+             longitude: {long:?}, comment: {comment:?} }}),",
+            zone = convert_bad_chars(&row[2]),
+            codes = row[0].split(',').collect::<Vec<_>>(),
+            lat = parse_coordinate(&coordinates[..split]),
+            long = parse_coordinate(&coordinates[split..]),
+            comment = row.get(3).map_or("", String::as_str),
+        )?;
+    }
+    writeln!(timezone_file, "];\n")?;
+
+    let country_rows = read_tab_file(&tz_dir.join("iso3166.tab"))?;
+    writeln!(
+        timezone_file,
+        "/// Codes and names of countries, in the order of `iso3166.tab`\n\
+         pub(crate) static COUNTRY_NAMES: [(&str, &str); {}] = [",
+        country_rows.len()
+    )?;
+    for row in country_rows {
+        writeln!(timezone_file, "    ({:?}, {:?}),", row[0], row[1])?;
+    }
+    writeln!(timezone_file, "];")
+}
+
 /// Stub module because filter-by-regex feature is not enabled
 #[cfg(not(feature = "filter-by-regex"))]
 mod filter {
@@ -439,14 +816,17 @@ mod filter {
             }
         }
 
-        // Actually do the filtering.
+        // Actually do the filtering, keeping the zones the crate refers to by name.
+        let required = ["UTC", "Etc/UTC", "Etc/GMT"];
         table
             .links
-            .retain(|k, v| keep.contains(k) || keep.contains(v));
+            .retain(|k, v| keep.contains(k) || keep.contains(v) || required.contains(&k.as_str()));
 
-        table
-            .zonesets
-            .retain(|k, _| filter_regex.is_match(&k) || keep.iter().any(|s| k.starts_with(s)));
+        table.zonesets.retain(|k, _| {
+            filter_regex.is_match(&k)
+                || keep.iter().any(|s| k.starts_with(s))
+                || required.contains(&k.as_str())
+        });
     }
 }
 
@@ -493,11 +873,25 @@ pub fn main() {
     }
 
     let mut table = table.build();
+    let tz_dir =
+        Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| String::new())).join("tz");
+    if cfg!(feature = "drop-backward-links") {
+        drop_backward_links(&mut table, &parser, &tz_dir.join("backward"));
+    }
     filter::maybe_filter_timezone_table(&mut table);
 
     let timezone_path = Path::new(&env::var("OUT_DIR").unwrap()).join("timezones.rs");
     let mut timezone_file = File::create(&timezone_path).unwrap();
     write_timezone_file(&mut timezone_file, &table).unwrap();
+    write_location_tables(&mut timezone_file, &table, &tz_dir)
+        .unwrap_or_else(|e| panic!("cannot read the zone tables in {}: {}", tz_dir.display(), e));
+    writeln!(
+        timezone_file,
+        "\n/// Release of the tz database, like `2024a`\n\
+         pub(crate) static DATABASE_VERSION: Option<&str> = {:?};",
+        database_version(&tz_dir),
+    )
+    .unwrap();
 
     let directory_path = Path::new(&env::var("OUT_DIR").unwrap()).join("directory.rs");
     let mut directory_file = File::create(&directory_path).unwrap();
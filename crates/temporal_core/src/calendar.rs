@@ -1,27 +1,145 @@
-use std::str::FromStr;
+use alloc::{
+    format,
+    string::{String, ToString},
+};
+use core::{
+    fmt::{self, Debug, Display},
+    ops::Deref,
+    str::FromStr,
+};
 
-use crate::{duration::NominalDuration, iso::IsoDate};
+use crate::{
+    duration::NominalDuration,
+    error::{ParseError, ParseErrorKind},
+    iso::{parse_two_digit, Cursor, IsoDate},
+};
 
 #[derive(Debug, Clone, Copy)]
 pub enum Calendar {
     Iso8601,
-    Persian,
+    Chinese,
+    Dangi,
+    Coptic,
+    Ethiopic,
+    EthiopicAmeteAlem,
+    Indian,
+    /// A user defined calendar, see [`Calendar::register`]
+    Custom(CustomCalendar),
+}
+
+impl Calendar {
+    /// Finds the builtin calendar of an id
+    fn builtin(s: &str) -> Option<Self> {
+        Some(match s {
+            "iso8601" => Self::Iso8601,
+            "chinese" => Self::Chinese,
+            "dangi" => Self::Dangi,
+            "coptic" => Self::Coptic,
+            "ethiopic" => Self::Ethiopic,
+            "ethioaa" => Self::EthiopicAmeteAlem,
+            "indian" => Self::Indian,
+            _ => return None,
+        })
+    }
+}
+
+/// Writes the id of the calendar, like `coptic`
+impl Display for Calendar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
 }
 
 impl FromStr for Calendar {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s {
-            "iso8601" => Self::Iso8601,
-            "persian" => Self::Persian,
-            _ => return Err(()),
-        })
+        Self::builtin(s)
+            .or_else(|| CustomCalendar::find(s).map(Self::Custom))
+            .ok_or_else(|| ParseError::new(ParseErrorKind::UnknownCalendar(s.to_string()), 0))
+    }
+}
+
+/// Code of a month, like `M05`, or `M05L` for a leap month, stored inline
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MonthCode([u8; 4]);
+
+impl MonthCode {
+    /// Panics if `number` is not in `1..=99`
+    pub fn new(number: u8, is_leap: bool) -> Self {
+        assert!((1..=99).contains(&number));
+        Self([
+            b'M',
+            b'0' + number / 10,
+            b'0' + number % 10,
+            if is_leap { b'L' } else { 0 },
+        ])
+    }
+
+    pub fn number(&self) -> u8 {
+        (self.0[1] - b'0') * 10 + self.0[2] - b'0'
+    }
+
+    pub fn is_leap(&self) -> bool {
+        self.0[3] == b'L'
+    }
+
+    pub fn as_str(&self) -> &str {
+        let len = if self.is_leap() { 4 } else { 3 };
+        core::str::from_utf8(&self.0[..len]).unwrap()
+    }
+}
+
+impl FromStr for MonthCode {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut cur = Cursor::new(s);
+        cur.expect('M', "'M'")?;
+        let number = parse_two_digit(&mut cur)?;
+        if number == 0 {
+            return Err(ParseError::new(ParseErrorKind::OutOfRange, 1));
+        }
+        let is_leap = cur.eat('L');
+        cur.finish()?;
+        Ok(Self::new(number, is_leap))
+    }
+}
+
+impl Deref for MonthCode {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PartialEq<str> for MonthCode {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for MonthCode {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl Debug for MonthCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl Display for MonthCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
     }
 }
 
 pub struct Era {
-    pub name: String,
+    pub name: &'static str,
     pub year: u32,
 }
 
@@ -30,15 +148,25 @@ pub enum FromYMDResult {
     OverflowConstrained(IsoDate),
 }
 
+impl FromYMDResult {
+    pub fn constrain(self) -> IsoDate {
+        match self {
+            FromYMDResult::Normal(x) | FromYMDResult::OverflowConstrained(x) => x,
+        }
+    }
+}
+
 pub trait CalendarProtocol {
-    fn id(&self) -> String;
+    fn id(&self) -> &str;
     fn era(&self, iso_date: IsoDate) -> Option<Era>;
     fn year(&self, iso_date: IsoDate) -> i32;
     fn month(&self, iso_date: IsoDate) -> u32;
-    fn month_code(&self, iso_date: IsoDate) -> String;
+    fn month_code(&self, iso_date: IsoDate) -> MonthCode;
     fn day(&self, iso_date: IsoDate) -> u32;
     fn day_of_week(&self, iso_date: IsoDate) -> u32;
     fn day_of_year(&self, iso_date: IsoDate) -> u32;
+    /// Week of the year. Calendars without their own week numbering, like all the builtin
+    /// ones, use the weeks of the ISO week date.
     fn week_of_year(&self, iso_date: IsoDate) -> u32;
     fn days_in_week(&self, iso_date: IsoDate) -> u32;
     fn days_in_month(&self, iso_date: IsoDate) -> u32;
@@ -47,84 +175,202 @@ pub trait CalendarProtocol {
     fn in_leap_year(&self, iso_date: IsoDate) -> bool;
     fn from_ymd(&self, year: i32, month: u32, day: u32) -> FromYMDResult;
     fn date_add(&self, iso_date: IsoDate, dur: NominalDuration) -> FromYMDResult;
+
+    /// Returns the ordinal month of the month with the given month code number in the
+    /// year, or `None` if the year doesn't have that month. Calendars with leap months
+    /// should override this.
+    fn ordinal_month(&self, year: i32, number: u32, is_leap: bool) -> Option<u32> {
+        let months = self.months_in_year(self.from_ymd(year, 1, 1).constrain());
+        (!is_leap && number <= months).then_some(number)
+    }
+
+    /// Returns the year of the given era year. Calendars with eras should override this.
+    fn year_from_era(&self, era: &str, era_year: u32) -> Option<i32> {
+        let _ = (era, era_year);
+        None
+    }
+
+    /// Resolves a date from the fields. A leap month code which doesn't exist in the
+    /// year is constrained to the month with the same number.
+    fn date_from_fields(&self, fields: &DateFields) -> Result<FromYMDResult, DateFieldsError> {
+        let year = fields::resolve_year(self, fields)?;
+        let (month, constrained) = fields::resolve_month(self, year, fields)?;
+        let day = fields.day.ok_or(DateFieldsError::MissingField("day"))?;
+        Ok(fields::with_constrained(
+            self.from_ymd(year, month, day),
+            constrained,
+        ))
+    }
+
+    /// Resolves the first day of a month from the fields, ignoring the day.
+    fn year_month_from_fields(
+        &self,
+        fields: &DateFields,
+    ) -> Result<FromYMDResult, DateFieldsError> {
+        self.date_from_fields(&DateFields {
+            day: Some(1),
+            ..fields.clone()
+        })
+    }
+
+    /// Resolves a month and day from the fields, returned as the ISO date of the latest
+    /// year, which starts before the end of ISO year 1972, having that month and day.
+    fn month_day_from_fields(&self, fields: &DateFields) -> Result<FromYMDResult, DateFieldsError> {
+        fields::month_day_from_fields(self, fields)
+    }
+
+    /// Merges `additional` into `fields`, dropping fields which are redundant with the
+    /// ones in `additional`.
+    fn merge_fields(&self, fields: &DateFields, additional: &DateFields) -> DateFields {
+        fields::merge_fields(fields, additional)
+    }
 }
 
 pub(crate) struct IsoCalendar;
 
+mod chinese;
+mod coptic;
+mod fields;
 mod impls;
+mod indian;
+mod registry;
 
-impl Calendar {
-    fn to_trait_obj(self) -> Box<dyn CalendarProtocol> {
-        match self {
-            Calendar::Iso8601 => Box::new(IsoCalendar),
-            Calendar::Persian => todo!(),
+use chinese::ChineseBasedCalendar;
+use coptic::CopticBasedCalendar;
+pub use fields::{DateFields, DateFieldsError};
+use indian::IndianCalendar;
+pub use registry::{CalendarRegistrationError, CustomCalendar};
+
+/// Adds months to a year and month, in a calendar with a fixed number of months per year
+fn balance_year_month(year: i32, month: u32, months: i32, months_in_year: u32) -> (i32, u32) {
+    let months_in_year = months_in_year as i64;
+    let total = year as i64 * months_in_year + month as i64 - 1 + months as i64;
+    (
+        total.div_euclid(months_in_year) as i32,
+        total.rem_euclid(months_in_year) as u32 + 1,
+    )
+}
+
+/// Week of the ISO week date, which the builtin calendars use as their week of year.
+/// Weeks start on Monday, and the first week of a year is the one with its first Thursday.
+fn iso_week_of_year(iso_date: IsoDate) -> u32 {
+    let day = iso_date.to_epoch_day();
+    // 1970-01-01 is a Thursday
+    let thursday = day - (day + 3).rem_euclid(7) + 3;
+    let year = IsoDate::from_epoch_day(thursday).year();
+    let new_year = IsoDate::new_unchecked(year, 1, 1).to_epoch_day();
+    ((thursday - new_year) / 7 + 1) as u32
+}
+
+/// Calls the method on the calendar implementation, statically dispatched for builtin
+/// calendars
+macro_rules! dispatch {
+    ($self:ident.$method:ident($($arg:expr),*)) => {
+        match $self {
+            Calendar::Iso8601 => (&IsoCalendar).$method($($arg),*),
+            Calendar::Chinese => (&ChineseBasedCalendar::CHINESE).$method($($arg),*),
+            Calendar::Dangi => (&ChineseBasedCalendar::DANGI).$method($($arg),*),
+            Calendar::Coptic => (&CopticBasedCalendar::Coptic).$method($($arg),*),
+            Calendar::Ethiopic => (&CopticBasedCalendar::Ethiopic).$method($($arg),*),
+            Calendar::EthiopicAmeteAlem => {
+                (&CopticBasedCalendar::EthiopicAmeteAlem).$method($($arg),*)
+            }
+            Calendar::Indian => (&IndianCalendar).$method($($arg),*),
+            Calendar::Custom(c) => c.get().$method($($arg),*),
         }
-    }
+    };
 }
 
 #[allow(unused_variables)]
 impl CalendarProtocol for Calendar {
-    fn id(&self) -> String {
-        self.to_trait_obj().id()
+    fn id(&self) -> &str {
+        dispatch!(self.id())
     }
 
     fn era(&self, iso_date: IsoDate) -> Option<Era> {
-        self.to_trait_obj().era(iso_date)
+        dispatch!(self.era(iso_date))
     }
 
     fn year(&self, iso_date: IsoDate) -> i32 {
-        self.to_trait_obj().year(iso_date)
+        dispatch!(self.year(iso_date))
     }
 
     fn month(&self, iso_date: IsoDate) -> u32 {
-        self.to_trait_obj().month(iso_date)
+        dispatch!(self.month(iso_date))
     }
 
-    fn month_code(&self, iso_date: IsoDate) -> String {
-        self.to_trait_obj().month_code(iso_date)
+    fn month_code(&self, iso_date: IsoDate) -> MonthCode {
+        dispatch!(self.month_code(iso_date))
     }
 
     fn day(&self, iso_date: IsoDate) -> u32 {
-        self.to_trait_obj().day(iso_date)
+        dispatch!(self.day(iso_date))
     }
 
     fn day_of_week(&self, iso_date: IsoDate) -> u32 {
-        self.to_trait_obj().day_of_week(iso_date)
+        dispatch!(self.day_of_week(iso_date))
     }
 
     fn day_of_year(&self, iso_date: IsoDate) -> u32 {
-        self.to_trait_obj().day_of_year(iso_date)
+        dispatch!(self.day_of_year(iso_date))
     }
 
     fn week_of_year(&self, iso_date: IsoDate) -> u32 {
-        self.to_trait_obj().week_of_year(iso_date)
+        dispatch!(self.week_of_year(iso_date))
     }
 
     fn days_in_week(&self, iso_date: IsoDate) -> u32 {
-        self.to_trait_obj().days_in_week(iso_date)
+        dispatch!(self.days_in_week(iso_date))
     }
 
     fn days_in_month(&self, iso_date: IsoDate) -> u32 {
-        self.to_trait_obj().days_in_month(iso_date)
+        dispatch!(self.days_in_month(iso_date))
     }
 
     fn days_in_year(&self, iso_date: IsoDate) -> u32 {
-        self.to_trait_obj().days_in_year(iso_date)
+        dispatch!(self.days_in_year(iso_date))
     }
 
     fn months_in_year(&self, iso_date: IsoDate) -> u32 {
-        self.to_trait_obj().months_in_year(iso_date)
+        dispatch!(self.months_in_year(iso_date))
     }
 
     fn in_leap_year(&self, iso_date: IsoDate) -> bool {
-        self.to_trait_obj().in_leap_year(iso_date)
+        dispatch!(self.in_leap_year(iso_date))
     }
 
     fn from_ymd(&self, year: i32, month: u32, day: u32) -> FromYMDResult {
-        self.to_trait_obj().from_ymd(year, month, day)
+        dispatch!(self.from_ymd(year, month, day))
     }
 
     fn date_add(&self, iso_date: IsoDate, dur: NominalDuration) -> FromYMDResult {
-        self.to_trait_obj().date_add(iso_date, dur)
+        dispatch!(self.date_add(iso_date, dur))
+    }
+
+    fn ordinal_month(&self, year: i32, number: u32, is_leap: bool) -> Option<u32> {
+        dispatch!(self.ordinal_month(year, number, is_leap))
+    }
+
+    fn year_from_era(&self, era: &str, era_year: u32) -> Option<i32> {
+        dispatch!(self.year_from_era(era, era_year))
+    }
+
+    fn date_from_fields(&self, fields: &DateFields) -> Result<FromYMDResult, DateFieldsError> {
+        dispatch!(self.date_from_fields(fields))
+    }
+
+    fn year_month_from_fields(
+        &self,
+        fields: &DateFields,
+    ) -> Result<FromYMDResult, DateFieldsError> {
+        dispatch!(self.year_month_from_fields(fields))
+    }
+
+    fn month_day_from_fields(&self, fields: &DateFields) -> Result<FromYMDResult, DateFieldsError> {
+        dispatch!(self.month_day_from_fields(fields))
+    }
+
+    fn merge_fields(&self, fields: &DateFields, additional: &DateFields) -> DateFields {
+        dispatch!(self.merge_fields(fields, additional))
     }
 }
@@ -0,0 +1,505 @@
+//! The `chinese` and `dangi` lunisolar calendars
+//!
+//! Both calendars are astronomical, so instead of computing new moons and solar terms
+//! at runtime we use precomputed tables, which makes results deterministic and cheap.
+//! The tables cover years whose new year falls in ISO years 1900 to 2100 inclusive,
+//! and are generated from ICU's astronomical calculations, using Beijing time for
+//! `chinese` and Seoul time for `dangi`. Outside of that range, months are
+//! approximated with mean lunations and a 19 year cycle of 7 leap months, which
+//! keeps arithmetic consistent but doesn't match the observed calendar. Such years are
+//! slightly longer than solar years, so their new year drifts later by about a day every
+//! 220 years, and far from the tables a year can start in a different ISO year than the
+//! one it's related to.
+
+use super::*;
+
+/// Packed data of a single year, indexed by its related ISO year:
+///
+/// * bits 0..=12: bit `i` is set if the ordinal month `i + 1` has 30 days, 29 otherwise
+/// * bits 13..=16: ordinal of the leap month, or 0 if the year has 12 months
+/// * bits 17..=22: days between January 1st of the related ISO year and the new year
+type PackedYear = u32;
+
+const FIRST_YEAR: i32 = 1900;
+const LAST_YEAR: i32 = 2100;
+
+/// Length of the mean synodic month, in micro days
+const MEAN_SYNODIC_MONTH: i64 = 29_530_589;
+
+#[rustfmt::skip]
+const CHINESE_DATA: [PackedYear; (LAST_YEAR - FIRST_YEAR + 1) as usize] = [
+    0x3d36d2, 0x620752, 0x4c0ea5, 0x38d64a, 0x5c064b, 0x440a9b, 0x30b556, 0x56056a,
+    0x400b59, 0x2a7752, 0x500752, 0x3afb25, 0x600b25, 0x480a4b, 0x32d4ab, 0x5802ad,
+    0x42056b, 0x2c8b69, 0x520da9, 0x3f1d92, 0x640e92, 0x4c0d25, 0x36fa4d, 0x5c0a56,
+    0x4602b6, 0x2eb5b5, 0x5606d4, 0x400ea9, 0x2c7e92, 0x500e92, 0x3aed26, 0x5e052b,
+    0x480a57, 0x32d2b6, 0x580b5a, 0x4406d4, 0x2e8ec9, 0x520749, 0x3d1693, 0x620a93,
+    0x4c052b, 0x34ea5b, 0x5a0aad, 0x46056a, 0x30bb55, 0x560ba4, 0x400b49, 0x2a7a93,
+    0x500a95, 0x39152d, 0x5e0536, 0x480aad, 0x34d5aa, 0x580db2, 0x440da4, 0x2e9d49,
+    0x540d4a, 0x3d2a95, 0x600a97, 0x4c0556, 0x36eab5, 0x5a0ad5, 0x4606d2, 0x30aea5,
+    0x560ea5, 0x40064a, 0x288c97, 0x4e0a9b, 0x3b155a, 0x5e056a, 0x480b69, 0x34d752,
+    0x5a0b52, 0x420b25, 0x2cb64b, 0x520a4b, 0x3d34ab, 0x6002ad, 0x4a056d, 0x36eb69,
+    0x5c0da9, 0x460d92, 0x30bd25, 0x560d25, 0x417a4d, 0x640a56, 0x4e02b6, 0x3905b5,
+    0x5e06d5, 0x480ea9, 0x34de92, 0x5a0e92, 0x440d26, 0x2c8a56, 0x500a57, 0x3d34d6,
+    0x62035a, 0x4a06d5, 0x36cec9, 0x5c0749, 0x460693, 0x2eb52b, 0x54052b, 0x3e0a5b,
+    0x2a755a, 0x4e056a, 0x391b55, 0x600ba4, 0x4a0b49, 0x32da93, 0x580a95, 0x42052d,
+    0x2caa6d, 0x500ab5, 0x3d55aa, 0x6205d2, 0x4c0da5, 0x36fd4a, 0x5c0e4a, 0x460c95,
+    0x30b52e, 0x540556, 0x3e0ab5, 0x2a75b2, 0x5006d2, 0x38eea5, 0x5e0f25, 0x4a064a,
+    0x32cc97, 0x5604ab, 0x40055b, 0x2c8ad6, 0x520b69, 0x3d9752, 0x620b52, 0x4c0b25,
+    0x36fa4b, 0x5a0a4b, 0x4404ab, 0x2ec55b, 0x5405ad, 0x3e0b6a, 0x2a7b52, 0x500d92,
+    0x3b1d25, 0x5e0d25, 0x480a55, 0x32d4ad, 0x5804b6, 0x4005b5, 0x2c8daa, 0x520ec9,
+    0x3f3e92, 0x620e92, 0x4c0d26, 0x36ea56, 0x5a0a57, 0x4404d6, 0x2ea6d5, 0x540755,
+    0x400749, 0x288e93, 0x4e0693, 0x39152b, 0x5e052b, 0x460a5b, 0x32d55a, 0x58056a,
+    0x420b65, 0x2cb74a, 0x520b49, 0x3d3a95, 0x620a95, 0x4a052d, 0x34eaad, 0x5a0ab5,
+    0x4605aa, 0x2eaba5, 0x540da5, 0x400d4a, 0x2a9c95, 0x4e0c96, 0x39194e, 0x5e0556,
+    0x480ab5, 0x32d5b2, 0x5806d2, 0x420ea5, 0x2eae4a, 0x50068b, 0x3b2c97, 0x6004ab,
+    0x4a055b, 0x34ead6, 0x5a0b6a, 0x460752, 0x30b725, 0x540b45, 0x3e0a8b, 0x28749b,
+    0x4e04ab,
+];
+
+#[rustfmt::skip]
+const DANGI_DATA: [PackedYear; (LAST_YEAR - FIRST_YEAR + 1) as usize] = [
+    0x3d36d2, 0x620752, 0x4c0ea5, 0x38d64a, 0x5c064b, 0x440a9b, 0x30b556, 0x56056a,
+    0x400b59, 0x2a7752, 0x500752, 0x3afb25, 0x600b25, 0x480a4b, 0x32d29b, 0x580aad,
+    0x44056a, 0x2c6b69, 0x520ba9, 0x3f1b52, 0x640d92, 0x4c0d25, 0x36da4d, 0x5c0956,
+    0x4602b5, 0x2eb5ad, 0x5606d4, 0x400da9, 0x2c7d92, 0x500e92, 0x3aed26, 0x5e0527,
+    0x480a57, 0x32d2b6, 0x580ada, 0x4406d4, 0x2e8ea9, 0x520749, 0x3d1693, 0x620a93,
+    0x4c052b, 0x34ea5b, 0x5a096d, 0x460b6a, 0x32bb54, 0x560ba4, 0x400b49, 0x2a7a93,
+    0x500a95, 0x39152b, 0x5e052d, 0x480aad, 0x34d56a, 0x580db2, 0x440da4, 0x2e9d49,
+    0x540d4a, 0x3d3a95, 0x620a96, 0x4c0556, 0x36eab5, 0x5a0ad5, 0x4606d2, 0x30aea5,
+    0x560ea5, 0x400e4a, 0x2a8c96, 0x4e0a9b, 0x3b1556, 0x5e056a, 0x480b59, 0x34d752,
+    0x5a0752, 0x420725, 0x2cb64b, 0x520a4b, 0x3d32ab, 0x6002ad, 0x4a056b, 0x36eb69,
+    0x5c0da9, 0x460d92, 0x30bb25, 0x560d25, 0x417a4d, 0x640a56, 0x4e02b6, 0x38f5ad,
+    0x6006d4, 0x480da9, 0x34dd92, 0x5a0e92, 0x440d26, 0x2c8a56, 0x500a57, 0x3d32b6,
+    0x620b5a, 0x4c06d4, 0x36cec9, 0x5c0749, 0x460693, 0x2eb527, 0x54052b, 0x3e0a5b,
+    0x2a755a, 0x4e036a, 0x391b55, 0x600ba4, 0x4a0b49, 0x32da93, 0x580a95, 0x42052d,
+    0x2c8a5d, 0x500aad, 0x3d55aa, 0x6205d2, 0x4c0da5, 0x36dd49, 0x5c0d4a, 0x460a95,
+    0x30b52d, 0x540556, 0x3e0ab5, 0x2a75aa, 0x5006d2, 0x38eea5, 0x5e0ea5, 0x4a0e4a,
+    0x34cc96, 0x560c9b, 0x42055a, 0x2c8ad5, 0x520b69, 0x3d9752, 0x620752, 0x4c0b25,
+    0x36f64b, 0x5a0a4b, 0x4404ab, 0x2ec55b, 0x54056d, 0x3e0b69, 0x2a7b52, 0x500d92,
+    0x3b1d25, 0x5e0d25, 0x480a4d, 0x32d4ad, 0x5802b6, 0x4005b5, 0x2c8da9, 0x520dc9,
+    0x3f3d92, 0x620e92, 0x4c0d26, 0x36ea56, 0x5a0a57, 0x4404d6, 0x2ea6b5, 0x5406d5,
+    0x400ec9, 0x2a8e92, 0x4e0693, 0x39152b, 0x5e052b, 0x460a5b, 0x32d55a, 0x58056a,
+    0x420b55, 0x2cb749, 0x520b49, 0x3d3a93, 0x620a95, 0x4a052d, 0x34eaad, 0x5a0ab5,
+    0x4605aa, 0x2eaba5, 0x540da5, 0x400d4a, 0x2a9a95, 0x4e0c95, 0x39152e, 0x5e0556,
+    0x480ab5, 0x32d5b2, 0x5806d2, 0x420ea5, 0x2ebe4a, 0x52064a, 0x3b2c97, 0x600cab,
+    0x4c055a, 0x34ead5, 0x5a0b69, 0x460752, 0x30aea5, 0x540b25, 0x3e064b, 0x289497,
+    0x4e04ab,
+];
+
+/// Names of the years in the 60 year cycle, combining a celestial stem and a terrestrial
+/// branch
+#[rustfmt::skip]
+const CYCLIC_YEARS: [&str; 60] = [
+    "jia-zi", "yi-chou", "bing-yin", "ding-mao", "wu-chen", "ji-si", "geng-wu", "xin-wei",
+    "ren-shen", "gui-you", "jia-xu", "yi-hai", "bing-zi", "ding-chou", "wu-yin", "ji-mao",
+    "geng-chen", "xin-si", "ren-wu", "gui-wei", "jia-shen", "yi-you", "bing-xu", "ding-hai",
+    "wu-zi", "ji-chou", "geng-yin", "xin-mao", "ren-chen", "gui-si", "jia-wu", "yi-wei",
+    "bing-shen", "ding-you", "wu-xu", "ji-hai", "geng-zi", "xin-chou", "ren-yin", "gui-mao",
+    "jia-chen", "yi-si", "bing-wu", "ding-wei", "wu-shen", "ji-you", "geng-xu", "xin-hai",
+    "ren-zi", "gui-chou", "jia-yin", "yi-mao", "bing-chen", "ding-si", "wu-wu", "ji-wei",
+    "geng-shen", "xin-you", "ren-xu", "gui-hai",
+];
+
+pub(crate) struct ChineseBasedCalendar {
+    id: &'static str,
+    data: &'static [PackedYear],
+}
+
+impl ChineseBasedCalendar {
+    pub(crate) const CHINESE: Self = Self {
+        id: "chinese",
+        data: &CHINESE_DATA,
+    };
+
+    pub(crate) const DANGI: Self = Self {
+        id: "dangi",
+        data: &DANGI_DATA,
+    };
+}
+
+#[derive(Clone, Copy)]
+struct YearInfo {
+    related_year: i32,
+    /// Epoch day of the first day of the year
+    new_year: i64,
+    month_lengths: u16,
+    months: u32,
+    leap_month: u32,
+}
+
+impl YearInfo {
+    fn days_in_month(&self, month: u32) -> u32 {
+        if self.month_lengths & (1 << (month - 1)) != 0 {
+            30
+        } else {
+            29
+        }
+    }
+
+    fn days_in_year(&self) -> u32 {
+        (1..=self.months).map(|m| self.days_in_month(m)).sum()
+    }
+
+    /// Epoch day of the first day of the given ordinal month
+    fn month_start(&self, month: u32) -> i64 {
+        self.new_year
+            + (1..month)
+                .map(|m| self.days_in_month(m) as i64)
+                .sum::<i64>()
+    }
+
+    /// Returns the month number in the month code, and if it is a leap month
+    fn month_code_parts(&self, month: u32) -> (u32, bool) {
+        if self.leap_month == 0 || month < self.leap_month {
+            (month, false)
+        } else {
+            (month - 1, month == self.leap_month)
+        }
+    }
+
+    /// Returns the ordinal month for a month code, if the year contains it
+    fn ordinal_month(&self, code: u32, is_leap: bool) -> Option<u32> {
+        if is_leap {
+            return (self.leap_month != 0 && self.leap_month - 1 == code)
+                .then_some(self.leap_month);
+        }
+        if self.leap_month != 0 && code >= self.leap_month {
+            Some(code + 1)
+        } else {
+            Some(code)
+        }
+    }
+}
+
+impl ChineseBasedCalendar {
+    fn unpack(&self, related_year: i32) -> YearInfo {
+        let packed = self.data[(related_year - FIRST_YEAR) as usize];
+        let leap_month = (packed >> 13) & 0xF;
+        YearInfo {
+            related_year,
+            new_year: IsoDate::new_unchecked(related_year, 1, 1).to_epoch_day()
+                + (packed >> 17) as i64,
+            month_lengths: (packed & 0x1FFF) as u16,
+            months: if leap_month == 0 { 12 } else { 13 },
+            leap_month,
+        }
+    }
+
+    /// Builds a year out of mean lunations, counted from the nearest end of the table
+    fn approximate(&self, related_year: i32) -> YearInfo {
+        let anchor = if related_year < FIRST_YEAR {
+            self.unpack(FIRST_YEAR)
+        } else {
+            let last = self.unpack(LAST_YEAR);
+            YearInfo {
+                related_year: LAST_YEAR + 1,
+                new_year: last.new_year + last.days_in_year() as i64,
+                ..last
+            }
+        };
+        let lunations = |year: i32| {
+            let years = (year - anchor.related_year) as i64;
+            12 * years + (7 * years).div_euclid(19)
+        };
+        let day =
+            |lunation: i64| anchor.new_year + (lunation * MEAN_SYNODIC_MONTH).div_euclid(1_000_000);
+        let first = lunations(related_year);
+        let months = (lunations(related_year + 1) - first) as u32;
+        let mut month_lengths = 0;
+        for i in 0..months {
+            let n = first + i as i64;
+            if day(n + 1) - day(n) == 30 {
+                month_lengths |= 1 << i;
+            }
+        }
+        YearInfo {
+            related_year,
+            new_year: day(first),
+            month_lengths,
+            months,
+            leap_month: if months == 13 { 7 } else { 0 },
+        }
+    }
+
+    fn year_info(&self, related_year: i32) -> YearInfo {
+        if (FIRST_YEAR..=LAST_YEAR).contains(&related_year) {
+            self.unpack(related_year)
+        } else {
+            self.approximate(related_year)
+        }
+    }
+
+    /// Returns the year, the ordinal month and the day of the given date
+    ///
+    /// The search starts from the ISO year of the date, and moves to the previous or next
+    /// year until one contains it, since approximated years drift away from the solar year.
+    fn locate(&self, iso_date: IsoDate) -> (YearInfo, u32, u32) {
+        let epoch_day = iso_date.to_epoch_day();
+        let mut related_year = iso_date.year();
+        let (info, mut day) = loop {
+            let info = self.year_info(related_year);
+            match u32::try_from(epoch_day - info.new_year) {
+                Err(_) => related_year -= 1,
+                Ok(day) if day >= info.days_in_year() => related_year += 1,
+                Ok(day) => break (info, day),
+            }
+        };
+        let mut month = 1;
+        while day >= info.days_in_month(month) {
+            day -= info.days_in_month(month);
+            month += 1;
+        }
+        (info, month, day + 1)
+    }
+}
+
+#[allow(unused)]
+impl CalendarProtocol for ChineseBasedCalendar {
+    fn id(&self) -> &str {
+        self.id
+    }
+
+    /// There is no era in these calendars, instead years are named in a 60 year cycle.
+    /// The name of the year is returned as the era name, and its position in the cycle
+    /// (starting from 1) as the era year.
+    fn era(&self, iso_date: IsoDate) -> Option<Era> {
+        let cyclic = (self.year(iso_date) - 4).rem_euclid(60) as usize;
+        Some(Era {
+            name: CYCLIC_YEARS[cyclic],
+            year: cyclic as u32 + 1,
+        })
+    }
+
+    /// Returns the related ISO year, that is the ISO year in which the year starts, except
+    /// for approximated years far from the tables
+    fn year(&self, iso_date: IsoDate) -> i32 {
+        self.locate(iso_date).0.related_year
+    }
+
+    fn month(&self, iso_date: IsoDate) -> u32 {
+        self.locate(iso_date).1
+    }
+
+    fn month_code(&self, iso_date: IsoDate) -> MonthCode {
+        let (info, month, _) = self.locate(iso_date);
+        let (code, is_leap) = info.month_code_parts(month);
+        MonthCode::new(code as u8, is_leap)
+    }
+
+    fn day(&self, iso_date: IsoDate) -> u32 {
+        self.locate(iso_date).2
+    }
+
+    fn day_of_week(&self, iso_date: IsoDate) -> u32 {
+        iso_date.to_icu_date().day_of_week() as u32
+    }
+
+    fn day_of_year(&self, iso_date: IsoDate) -> u32 {
+        let (info, _, _) = self.locate(iso_date);
+        (iso_date.to_epoch_day() - info.new_year) as u32 + 1
+    }
+
+    fn week_of_year(&self, iso_date: IsoDate) -> u32 {
+        iso_week_of_year(iso_date)
+    }
+
+    fn days_in_week(&self, iso_date: IsoDate) -> u32 {
+        7
+    }
+
+    fn days_in_month(&self, iso_date: IsoDate) -> u32 {
+        let (info, month, _) = self.locate(iso_date);
+        info.days_in_month(month)
+    }
+
+    fn days_in_year(&self, iso_date: IsoDate) -> u32 {
+        self.locate(iso_date).0.days_in_year()
+    }
+
+    fn months_in_year(&self, iso_date: IsoDate) -> u32 {
+        self.locate(iso_date).0.months
+    }
+
+    fn in_leap_year(&self, iso_date: IsoDate) -> bool {
+        self.locate(iso_date).0.leap_month != 0
+    }
+
+    /// `year` is the related ISO year and `month` is the ordinal month, so in a leap
+    /// year the month after the leap month is one more than its month code.
+    fn from_ymd(&self, year: i32, month: u32, day: u32) -> FromYMDResult {
+        let r_year = year.clamp(IsoDate::MIN_YEAR + 1, IsoDate::MAX_YEAR - 1);
+        let info = self.year_info(r_year);
+        let r_month = month.clamp(1, info.months);
+        let r_day = day.clamp(1, info.days_in_month(r_month));
+        let iso_date = IsoDate::from_epoch_day(info.month_start(r_month) + r_day as i64 - 1);
+        if (r_year, r_month, r_day) == (year, month, day) {
+            FromYMDResult::Normal(iso_date)
+        } else {
+            FromYMDResult::OverflowConstrained(iso_date)
+        }
+    }
+
+    fn ordinal_month(&self, year: i32, number: u32, is_leap: bool) -> Option<u32> {
+        if number > 12 {
+            return None;
+        }
+        self.year_info(year).ordinal_month(number, is_leap)
+    }
+
+    /// Years are added keeping the month code, and if the resulting year doesn't have
+    /// the leap month, the month with the same number is used. Then months are added,
+    /// the day is constrained to the resulting month, and weeks and days are added.
+    fn date_add(&self, iso_date: IsoDate, dur: NominalDuration) -> FromYMDResult {
+        let (info, month, day) = self.locate(iso_date);
+        let (code, is_leap) = info.month_code_parts(month);
+        let mut need_constrain = false;
+        let mut info = self.year_info(info.related_year + dur.years());
+        let mut month = match info.ordinal_month(code, is_leap) {
+            Some(m) => m,
+            None => {
+                need_constrain = true;
+                info.ordinal_month(code, false).unwrap()
+            }
+        };
+        let mut months = dur.months();
+        while months > 0 {
+            if month == info.months {
+                info = self.year_info(info.related_year + 1);
+                month = 1;
+            } else {
+                month += 1;
+            }
+            months -= 1;
+        }
+        while months < 0 {
+            if month == 1 {
+                info = self.year_info(info.related_year - 1);
+                month = info.months;
+            } else {
+                month -= 1;
+            }
+            months += 1;
+        }
+        let mut day = day;
+        if day > info.days_in_month(month) {
+            need_constrain = true;
+            day = info.days_in_month(month);
+        }
+        let epoch_day =
+            info.month_start(month) + day as i64 - 1 + 7 * dur.weeks() as i64 + dur.days() as i64;
+        let iso_date = IsoDate::from_epoch_day(epoch_day);
+        if need_constrain {
+            FromYMDResult::OverflowConstrained(iso_date)
+        } else {
+            FromYMDResult::Normal(iso_date)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Calendar, CalendarProtocol, IsoDate, NominalDuration, PlainDate};
+
+    #[test]
+    fn lunar_new_year() {
+        let date = IsoDate::new(2023, 1, 22).unwrap();
+        assert_eq!(Calendar::Chinese.year(date), 2023);
+        assert_eq!(Calendar::Chinese.month(date), 1);
+        assert_eq!(Calendar::Chinese.day(date), 1);
+        let date = IsoDate::new(2023, 1, 21).unwrap();
+        assert_eq!(Calendar::Chinese.year(date), 2022);
+        assert_eq!(Calendar::Chinese.month_code(date), "M12");
+        assert_eq!(Calendar::Chinese.day(date), 30);
+    }
+
+    #[test]
+    fn leap_month() {
+        let date = IsoDate::new(2023, 3, 22).unwrap();
+        assert_eq!(Calendar::Chinese.month(date), 3);
+        assert_eq!(Calendar::Chinese.month_code(date), "M02L");
+        assert_eq!(Calendar::Chinese.months_in_year(date), 13);
+        assert!(Calendar::Chinese.in_leap_year(date));
+        let era = Calendar::Chinese.era(date).unwrap();
+        assert_eq!(era.name, "gui-mao");
+        assert_eq!(era.year, 40);
+    }
+
+    #[test]
+    fn iso_weeks() {
+        let week = |y, m, d| Calendar::Chinese.week_of_year(IsoDate::new(y, m, d).unwrap());
+        assert_eq!(week(2023, 1, 22), 3);
+        assert_eq!(week(2021, 1, 3), 53);
+        assert_eq!(week(2021, 1, 4), 1);
+        assert_eq!(week(2024, 12, 30), 1);
+        assert_eq!(
+            Calendar::Iso8601.week_of_year(IsoDate::new(2020, 12, 31).unwrap()),
+            53
+        );
+    }
+
+    #[test]
+    fn chinese_and_dangi_differ() {
+        let date = IsoDate::new(1997, 2, 7).unwrap();
+        assert_eq!(Calendar::Chinese.month_code(date), "M01");
+        assert_eq!(Calendar::Dangi.month_code(date), "M12");
+    }
+
+    #[test]
+    fn from_ymd_round_trip() {
+        let date = PlainDate::from_ymd(2020, 5, 1, Calendar::Chinese).constrain();
+        assert_eq!(date.iso_date().month(), 5);
+        assert_eq!(date.iso_date().day(), 23);
+        assert_eq!(Calendar::Chinese.month_code(date.iso_date()), "M04L");
+        for year in [1850, 1950, 2150] {
+            let date = PlainDate::from_ymd(year, 12, 29, Calendar::Dangi).constrain();
+            assert_eq!(date.year(), year);
+            assert_eq!(date.month(), 12);
+        }
+    }
+
+    #[test]
+    fn approximated_years() {
+        let dates = [
+            (IsoDate::MIN_YEAR, 1, 1),
+            (IsoDate::MIN_YEAR, 12, 31),
+            (-10000, 1, 1),
+            (-10000, 12, 31),
+            (10000, 1, 1),
+            (10000, 12, 31),
+            (IsoDate::MAX_YEAR, 1, 1),
+            (IsoDate::MAX_YEAR, 12, 31),
+        ];
+        for calendar in [Calendar::Chinese, Calendar::Dangi] {
+            for (y, m, d) in dates {
+                let date = IsoDate::new(y, m, d).unwrap();
+                let (year, month, day) = (
+                    calendar.year(date),
+                    calendar.month(date),
+                    calendar.day(date),
+                );
+                assert!((1..=calendar.months_in_year(date)).contains(&month));
+                assert!((1..=calendar.days_in_month(date)).contains(&day));
+                if y.abs() == 10000 {
+                    let back = calendar.from_ymd(year, month, day).constrain();
+                    assert_eq!(back.to_epoch_day(), date.to_epoch_day());
+                }
+                // Move one month towards the middle of the range, from a day that every
+                // month has
+                let date = IsoDate::from_epoch_day(date.to_epoch_day() + 1 - day as i64);
+                let step = if y < 0 { 1 } else { -1 };
+                let dur = NominalDuration::from_date_units(0, step, 0, 0);
+                let moved = calendar.date_add(date, dur).constrain();
+                let expected = match (step, month) {
+                    (1, m) if m == calendar.months_in_year(date) => (year + 1, 1),
+                    (-1, 1) => (year - 1, calendar.months_in_year(moved)),
+                    _ => (year, (month as i32 + step) as u32),
+                };
+                assert_eq!((calendar.year(moved), calendar.month(moved)), expected);
+                assert_eq!(calendar.day(moved), 1);
+                let dur = NominalDuration::from_date_units(0, 0, step, 40 * step);
+                let moved = calendar.date_add(date, dur).constrain();
+                assert_eq!(moved.to_epoch_day() - date.to_epoch_day(), 47 * step as i64);
+            }
+        }
+    }
+}
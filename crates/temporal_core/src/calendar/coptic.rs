@@ -0,0 +1,239 @@
+//! The `coptic`, `ethiopic` and `ethioaa` calendars
+//!
+//! All of them have twelve months of 30 days, followed by a short 13th month of 5 days,
+//! or 6 days every fourth year. They only differ in the epoch.
+
+use super::*;
+
+#[derive(Clone, Copy)]
+pub(crate) enum CopticBasedCalendar {
+    Coptic,
+    /// Ethiopic calendar with the Amete Mihret era, and Amete Alem era before it
+    Ethiopic,
+    /// Ethiopic calendar with only the Amete Alem era
+    EthiopicAmeteAlem,
+}
+
+/// Years between the Amete Alem and Amete Mihret epochs
+const AMETE_ALEM_OFFSET: i32 = 5500;
+
+impl CopticBasedCalendar {
+    /// Epoch day of the day before the first day of year 1
+    fn epoch(self) -> i64 {
+        match self {
+            // 284-08-29 Julian
+            Self::Coptic => -615559,
+            // 8-08-29 Julian
+            Self::Ethiopic => -716368,
+            Self::EthiopicAmeteAlem => -716368 - AMETE_ALEM_OFFSET as i64 / 4 * 1461,
+        }
+    }
+
+    fn is_leap(year: i32) -> bool {
+        year.rem_euclid(4) == 3
+    }
+
+    fn days_in_month_of(year: i32, month: u32) -> u32 {
+        match month {
+            13 if Self::is_leap(year) => 6,
+            13 => 5,
+            _ => 30,
+        }
+    }
+
+    fn to_epoch_day(self, year: i32, month: u32, day: u32) -> i64 {
+        let year = year as i64;
+        self.epoch() + 365 * (year - 1) + year.div_euclid(4) + 30 * (month as i64 - 1) + day as i64
+    }
+
+    /// Returns the year, month and day of the given epoch day
+    fn date_of_epoch_day(self, epoch_day: i64) -> (i32, u32, u32) {
+        let year = (4 * (epoch_day - self.epoch() - 1) + 1463).div_euclid(1461) as i32;
+        let month = (epoch_day - self.to_epoch_day(year, 1, 1)).div_euclid(30) as u32 + 1;
+        let day = (epoch_day - self.to_epoch_day(year, month, 1)) as u32 + 1;
+        (year, month, day)
+    }
+
+    fn locate(self, iso_date: IsoDate) -> (i32, u32, u32) {
+        self.date_of_epoch_day(iso_date.to_epoch_day())
+    }
+
+    /// Range of years which are entirely representable as `IsoDate`
+    fn year_range(self) -> (i32, i32) {
+        let min = IsoDate::new_unchecked(IsoDate::MIN_YEAR, 1, 1).to_epoch_day();
+        let max = IsoDate::new_unchecked(IsoDate::MAX_YEAR, 12, 31).to_epoch_day();
+        (
+            self.date_of_epoch_day(min).0 + 1,
+            self.date_of_epoch_day(max).0 - 1,
+        )
+    }
+}
+
+#[allow(unused)]
+impl CalendarProtocol for CopticBasedCalendar {
+    fn id(&self) -> &str {
+        match self {
+            Self::Coptic => "coptic",
+            Self::Ethiopic => "ethiopic",
+            Self::EthiopicAmeteAlem => "ethioaa",
+        }
+    }
+
+    fn era(&self, iso_date: IsoDate) -> Option<Era> {
+        let year = self.year(iso_date);
+        let (name, year) = match self {
+            Self::Coptic if year > 0 => ("am", year),
+            Self::Coptic => ("bd", 1 - year),
+            Self::Ethiopic if year > 0 => ("am", year),
+            Self::Ethiopic | Self::EthiopicAmeteAlem => {
+                let year = match self {
+                    Self::Ethiopic => year + AMETE_ALEM_OFFSET,
+                    _ => year,
+                };
+                if year <= 0 {
+                    return None;
+                }
+                ("aa", year)
+            }
+        };
+        Some(Era {
+            name,
+            year: year as u32,
+        })
+    }
+
+    fn year(&self, iso_date: IsoDate) -> i32 {
+        self.locate(iso_date).0
+    }
+
+    fn month(&self, iso_date: IsoDate) -> u32 {
+        self.locate(iso_date).1
+    }
+
+    fn month_code(&self, iso_date: IsoDate) -> MonthCode {
+        MonthCode::new(self.month(iso_date) as u8, false)
+    }
+
+    fn day(&self, iso_date: IsoDate) -> u32 {
+        self.locate(iso_date).2
+    }
+
+    fn day_of_week(&self, iso_date: IsoDate) -> u32 {
+        iso_date.to_icu_date().day_of_week() as u32
+    }
+
+    fn day_of_year(&self, iso_date: IsoDate) -> u32 {
+        let (year, _, _) = self.locate(iso_date);
+        (iso_date.to_epoch_day() - self.to_epoch_day(year, 1, 1)) as u32 + 1
+    }
+
+    fn week_of_year(&self, iso_date: IsoDate) -> u32 {
+        iso_week_of_year(iso_date)
+    }
+
+    fn days_in_week(&self, iso_date: IsoDate) -> u32 {
+        7
+    }
+
+    fn days_in_month(&self, iso_date: IsoDate) -> u32 {
+        let (year, month, _) = self.locate(iso_date);
+        Self::days_in_month_of(year, month)
+    }
+
+    fn days_in_year(&self, iso_date: IsoDate) -> u32 {
+        if self.in_leap_year(iso_date) {
+            366
+        } else {
+            365
+        }
+    }
+
+    fn months_in_year(&self, iso_date: IsoDate) -> u32 {
+        13
+    }
+
+    fn in_leap_year(&self, iso_date: IsoDate) -> bool {
+        Self::is_leap(self.year(iso_date))
+    }
+
+    fn from_ymd(&self, year: i32, month: u32, day: u32) -> FromYMDResult {
+        let (min_year, max_year) = self.year_range();
+        let r_year = year.clamp(min_year, max_year);
+        let r_month = month.clamp(1, 13);
+        let r_day = day.clamp(1, Self::days_in_month_of(r_year, r_month));
+        let iso_date = IsoDate::from_epoch_day(self.to_epoch_day(r_year, r_month, r_day));
+        if (r_year, r_month, r_day) == (year, month, day) {
+            FromYMDResult::Normal(iso_date)
+        } else {
+            FromYMDResult::OverflowConstrained(iso_date)
+        }
+    }
+
+    fn year_from_era(&self, era: &str, era_year: u32) -> Option<i32> {
+        let era_year = era_year as i32;
+        match (self, era) {
+            (Self::Coptic, "am") | (Self::Ethiopic, "am") | (Self::EthiopicAmeteAlem, "aa") => {
+                Some(era_year)
+            }
+            (Self::Coptic, "bd") => Some(1 - era_year),
+            (Self::Ethiopic, "aa") => Some(era_year - AMETE_ALEM_OFFSET),
+            _ => None,
+        }
+    }
+
+    fn date_add(&self, iso_date: IsoDate, dur: NominalDuration) -> FromYMDResult {
+        let (year, month, mut day) = self.locate(iso_date);
+        let (year, month) = balance_year_month(year + dur.years(), month, dur.months(), 13);
+        let mut need_constrain = false;
+        if day > Self::days_in_month_of(year, month) {
+            need_constrain = true;
+            day = Self::days_in_month_of(year, month);
+        }
+        let epoch_day =
+            self.to_epoch_day(year, month, day) + 7 * dur.weeks() as i64 + dur.days() as i64;
+        let iso_date = IsoDate::from_epoch_day(epoch_day);
+        if need_constrain {
+            FromYMDResult::OverflowConstrained(iso_date)
+        } else {
+            FromYMDResult::Normal(iso_date)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Calendar, CalendarProtocol, IsoDate, PlainDate};
+
+    #[test]
+    fn thirteenth_month() {
+        let date = IsoDate::new(2023, 9, 11).unwrap();
+        assert_eq!(Calendar::Ethiopic.year(date), 2015);
+        assert_eq!(Calendar::Ethiopic.month_code(date), "M13");
+        assert_eq!(Calendar::Ethiopic.day(date), 6);
+        assert_eq!(Calendar::Ethiopic.days_in_month(date), 6);
+        assert_eq!(Calendar::Ethiopic.week_of_year(date), 37);
+        assert!(Calendar::Ethiopic.in_leap_year(date));
+        assert_eq!(Calendar::EthiopicAmeteAlem.year(date), 7515);
+        assert_eq!(Calendar::Coptic.year(date), 1739);
+        let date = IsoDate::new(2023, 9, 12).unwrap();
+        assert_eq!(Calendar::Coptic.year(date), 1740);
+        assert_eq!(Calendar::Coptic.month(date), 1);
+        assert_eq!(Calendar::Coptic.day(date), 1);
+    }
+
+    #[test]
+    fn eras() {
+        let date = IsoDate::new(1, 1, 1).unwrap();
+        let era = Calendar::Ethiopic.era(date).unwrap();
+        assert_eq!((era.name, era.year), ("aa", 5493));
+        let era = Calendar::Coptic.era(date).unwrap();
+        assert_eq!((era.name, era.year), ("bd", 284));
+    }
+
+    #[test]
+    fn constrain_short_month() {
+        let date = PlainDate::from_ymd(2016, 13, 6, Calendar::Ethiopic).constrain();
+        assert_eq!(date.month(), 13);
+        assert_eq!(Calendar::Ethiopic.day(date.iso_date()), 5);
+    }
+}
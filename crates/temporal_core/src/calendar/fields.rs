@@ -0,0 +1,262 @@
+//! Resolving dates from a bag of possibly partial and redundant fields
+
+use super::*;
+
+/// Fields of a date, as used by [`CalendarProtocol::date_from_fields`] and
+/// [`PlainDate::with`](crate::PlainDate::with)
+///
+/// Fields are redundant: a month can be given by `month` or `month_code`, and a year by
+/// `year` or `era` and `era_year`. If both are present they should agree.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DateFields {
+    pub year: Option<i32>,
+    pub month: Option<u32>,
+    pub month_code: Option<String>,
+    pub day: Option<u32>,
+    pub era: Option<String>,
+    pub era_year: Option<u32>,
+}
+
+#[non_exhaustive]
+#[derive(Debug, PartialEq, Eq)]
+pub enum DateFieldsError {
+    MissingField(&'static str),
+    InvalidMonthCode(String),
+    UnknownEra(String),
+    /// `month` and `month_code` refer to different months
+    InconsistentMonth,
+    /// `year` and `era` with `era_year` refer to different years
+    InconsistentYear,
+}
+
+/// Splits a month code like `M05L` into its number and whether it is a leap month
+fn parse_month_code(code: &str) -> Option<(u32, bool)> {
+    let code: MonthCode = code.parse().ok()?;
+    Some((code.number().into(), code.is_leap()))
+}
+
+pub(super) fn resolve_year<C: CalendarProtocol + ?Sized>(
+    calendar: &C,
+    fields: &DateFields,
+) -> Result<i32, DateFieldsError> {
+    use DateFieldsError::*;
+    let from_era = match (&fields.era, fields.era_year) {
+        (Some(era), Some(era_year)) => Some(
+            calendar
+                .year_from_era(era, era_year)
+                .ok_or_else(|| UnknownEra(era.clone()))?,
+        ),
+        (Some(_), None) => return Err(MissingField("era_year")),
+        (None, Some(_)) => return Err(MissingField("era")),
+        (None, None) => None,
+    };
+    match (fields.year, from_era) {
+        (Some(year), Some(from_era)) if year != from_era => Err(InconsistentYear),
+        (Some(year), _) | (None, Some(year)) => Ok(year),
+        (None, None) => Err(MissingField("year")),
+    }
+}
+
+/// Returns the ordinal month, and whether it was constrained because the year doesn't
+/// have the leap month of the month code
+pub(super) fn resolve_month<C: CalendarProtocol + ?Sized>(
+    calendar: &C,
+    year: i32,
+    fields: &DateFields,
+) -> Result<(u32, bool), DateFieldsError> {
+    use DateFieldsError::*;
+    let code = match &fields.month_code {
+        Some(code) => code,
+        None => {
+            return fields
+                .month
+                .map(|m| (m, false))
+                .ok_or(MissingField("month"))
+        }
+    };
+    let (number, is_leap) = parse_month_code(code).ok_or_else(|| InvalidMonthCode(code.clone()))?;
+    let (month, constrained) = match calendar.ordinal_month(year, number, is_leap) {
+        Some(month) => (month, false),
+        None if is_leap => (
+            calendar
+                .ordinal_month(year, number, false)
+                .ok_or_else(|| InvalidMonthCode(code.clone()))?,
+            true,
+        ),
+        None => return Err(InvalidMonthCode(code.clone())),
+    };
+    if fields.month.is_some_and(|m| m != month) {
+        return Err(InconsistentMonth);
+    }
+    Ok((month, constrained))
+}
+
+pub(super) fn with_constrained(result: FromYMDResult, constrained: bool) -> FromYMDResult {
+    match result {
+        FromYMDResult::Normal(x) if constrained => FromYMDResult::OverflowConstrained(x),
+        r => r,
+    }
+}
+
+/// Number of calendar years to search backward for a reference year of a month day
+const MONTH_DAY_SEARCH_YEARS: i32 = 200;
+
+pub(super) fn month_day_from_fields<C: CalendarProtocol + ?Sized>(
+    calendar: &C,
+    fields: &DateFields,
+) -> Result<FromYMDResult, DateFieldsError> {
+    use DateFieldsError::*;
+    let day = fields.day.ok_or(MissingField("day"))?;
+    // With a year, the month code and day are found in that year, as `month` alone is
+    // only meaningful with a year.
+    let (number, is_leap, day, mut constrained) = if fields.year.is_some() || fields.era.is_some() {
+        let year = resolve_year(calendar, fields)?;
+        let (month, constrained) = resolve_month(calendar, year, fields)?;
+        let date = calendar.from_ymd(year, month, day);
+        let constrained = constrained || matches!(date, FromYMDResult::OverflowConstrained(_));
+        let date = date.constrain();
+        let code = calendar.month_code(date);
+        let (number, is_leap) = (code.number().into(), code.is_leap());
+        (number, is_leap, calendar.day(date), constrained)
+    } else {
+        let code = fields
+            .month_code
+            .as_ref()
+            .ok_or(MissingField("month_code"))?;
+        let (number, is_leap) =
+            parse_month_code(code).ok_or_else(|| InvalidMonthCode(code.clone()))?;
+        (number, is_leap, day, false)
+    };
+    // The reference year is the latest year, which starts before the end of ISO year
+    // 1972, having the month with that day.
+    let start = calendar.year(IsoDate::new_unchecked(1972, 12, 31));
+    let mut best = None;
+    for year in (start - MONTH_DAY_SEARCH_YEARS..=start).rev() {
+        let month = match calendar.ordinal_month(year, number, is_leap) {
+            Some(month) => month,
+            None => continue,
+        };
+        match calendar.from_ymd(year, month, day) {
+            FromYMDResult::Normal(x) => {
+                best = Some(x);
+                break;
+            }
+            FromYMDResult::OverflowConstrained(x) => {
+                best.get_or_insert(x);
+            }
+        }
+    }
+    let date = match best {
+        Some(x) if calendar.day(x) == day => x,
+        Some(x) => {
+            constrained = true;
+            x
+        }
+        None if is_leap => {
+            constrained = true;
+            let month = calendar
+                .ordinal_month(start, number, false)
+                .ok_or(InvalidMonthCode(format!("M{:02}L", number)))?;
+            calendar.from_ymd(start, month, day).constrain()
+        }
+        None => return Err(InvalidMonthCode(format!("M{:02}", number))),
+    };
+    Ok(if constrained {
+        FromYMDResult::OverflowConstrained(date)
+    } else {
+        FromYMDResult::Normal(date)
+    })
+}
+
+/// Merges `additional` into `fields`, such that fields from `additional` override
+/// all the fields they are redundant with, to prevent spurious inconsistencies.
+pub(super) fn merge_fields(fields: &DateFields, additional: &DateFields) -> DateFields {
+    let mut result = fields.clone();
+    if additional.month.is_some() || additional.month_code.is_some() {
+        result.month = additional.month;
+        result.month_code = additional.month_code.clone();
+    }
+    if additional.year.is_some() || additional.era.is_some() || additional.era_year.is_some() {
+        result.year = additional.year;
+        result.era = additional.era.clone();
+        result.era_year = additional.era_year;
+    }
+    if additional.day.is_some() {
+        result.day = additional.day;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Calendar, CalendarProtocol, DateFields, DateFieldsError, FromYMDResult};
+
+    fn fields(year: Option<i32>, month: Option<u32>, code: Option<&str>, day: u32) -> DateFields {
+        DateFields {
+            year,
+            month,
+            month_code: code.map(|c| c.to_string()),
+            day: Some(day),
+            ..DateFields::default()
+        }
+    }
+
+    #[test]
+    fn month_and_month_code() {
+        let cal = Calendar::Iso8601;
+        let r = cal.date_from_fields(&fields(Some(2000), Some(3), Some("M03"), 5));
+        assert!(matches!(r, Ok(FromYMDResult::Normal(x)) if x.month() == 3));
+        let r = cal.date_from_fields(&fields(Some(2000), Some(4), Some("M03"), 5));
+        assert_eq!(r.err(), Some(DateFieldsError::InconsistentMonth));
+        let r = cal.date_from_fields(&fields(Some(2000), None, Some("M13"), 5));
+        assert_eq!(
+            r.err(),
+            Some(DateFieldsError::InvalidMonthCode("M13".to_string()))
+        );
+        let r = cal.date_from_fields(&fields(Some(2000), None, None, 5));
+        assert_eq!(r.err(), Some(DateFieldsError::MissingField("month")));
+    }
+
+    #[test]
+    fn leap_month_code() {
+        let cal = Calendar::Chinese;
+        let r = cal.date_from_fields(&fields(Some(2023), None, Some("M02L"), 1));
+        assert!(matches!(r, Ok(FromYMDResult::Normal(x)) if (x.month(), x.day()) == (3, 22)));
+        let r = cal.date_from_fields(&fields(Some(2024), None, Some("M02L"), 1));
+        assert!(
+            matches!(r, Ok(FromYMDResult::OverflowConstrained(x)) if cal.month_code(x) == "M02")
+        );
+    }
+
+    #[test]
+    fn era_and_era_year() {
+        let cal = Calendar::Ethiopic;
+        let mut f = fields(None, Some(1), None, 1);
+        f.era = Some("aa".to_string());
+        f.era_year = Some(5493);
+        let date = cal.date_from_fields(&f).unwrap().constrain();
+        assert_eq!(cal.year(date), -7);
+        f.year = Some(-6);
+        assert_eq!(
+            cal.date_from_fields(&f).err(),
+            Some(DateFieldsError::InconsistentYear)
+        );
+    }
+
+    #[test]
+    fn month_day() {
+        let cal = Calendar::Iso8601;
+        let r = cal.month_day_from_fields(&fields(None, None, Some("M02"), 29));
+        assert!(matches!(r, Ok(FromYMDResult::Normal(x)) if x.year() == 1972));
+        let cal = Calendar::Chinese;
+        let r = cal.month_day_from_fields(&fields(None, None, Some("M02L"), 1));
+        assert!(matches!(r, Ok(FromYMDResult::Normal(x)) if cal.month_code(x) == "M02L"));
+    }
+
+    #[test]
+    fn merge() {
+        let base = fields(Some(2000), Some(3), Some("M03"), 5);
+        let merged = Calendar::Iso8601.merge_fields(&base, &fields(None, Some(4), None, 6));
+        assert_eq!(merged, fields(Some(2000), Some(4), None, 6));
+    }
+}
@@ -1,11 +1,11 @@
-use std::cmp::{max, min};
+use core::cmp::{max, min};
 
 use super::*;
 
 #[allow(unused)]
 impl CalendarProtocol for IsoCalendar {
-    fn id(&self) -> String {
-        "iso8601".to_string()
+    fn id(&self) -> &str {
+        "iso8601"
     }
 
     fn era(&self, iso_date: IsoDate) -> Option<Era> {
@@ -20,8 +20,8 @@ impl CalendarProtocol for IsoCalendar {
         iso_date.month().into()
     }
 
-    fn month_code(&self, iso_date: IsoDate) -> String {
-        format!("M{}", iso_date.month())
+    fn month_code(&self, iso_date: IsoDate) -> MonthCode {
+        MonthCode::new(iso_date.month(), false)
     }
 
     fn day(&self, iso_date: IsoDate) -> u32 {
@@ -37,7 +37,7 @@ impl CalendarProtocol for IsoCalendar {
     }
 
     fn week_of_year(&self, iso_date: IsoDate) -> u32 {
-        todo!()
+        iso_week_of_year(iso_date)
     }
 
     fn days_in_week(&self, iso_date: IsoDate) -> u32 {
@@ -76,54 +76,28 @@ impl CalendarProtocol for IsoCalendar {
     }
 
     fn date_add(&self, iso_date: IsoDate, dur: NominalDuration) -> FromYMDResult {
-        /*fn balance_year_month(year: i32, month: i32) -> (i32, u8) {
-            (
-                year + (month - 1).div_euclid(12),
-                (month - 1).rem_euclid(12).try_into().unwrap(),
-            )
-        }
-        let (mut r_year, mut r_month) = balance_year_month(
-            iso_date.year + dur.years(),
-            iso_date.month() as i32 + dur.months(),
+        let (year, month) = balance_year_month(
+            iso_date.year() + dur.years(),
+            iso_date.month().into(),
+            dur.months(),
+            12,
         );
+        let month = month as u8;
+        let mut day = iso_date.day();
         let mut need_constrain = false;
-        let mut r_day: i32 = iso_date.day().into();
-        if self.days_in_month(r_year, r_month, 1) > iso_date.day().into() {
+        let max_day = self.days_in_month(IsoDate::new_unchecked(year, month, 1)) as u16;
+        if day > max_day {
             need_constrain = true;
-            r_day = self.days_in_month(r_year, r_month, 1).try_into().unwrap();
-        }
-        r_day += 7 * dur.weeks();
-        r_day += dur.days();
-        loop {
-            let dim = self.days_in_month(r_year, r_month, 1).try_into().unwrap();
-            if r_day <= dim {
-                break;
-            }
-            r_day -= dim;
-            r_month += 1;
-            if r_month == 13 {
-                r_month = 1;
-                r_year += 1;
-            }
+            day = max_day;
         }
-        while r_day < 1 {
-            r_month -= 1;
-            if r_month == 0 {
-                r_month = 12;
-                r_year -= 1;
-            }
-            r_day += self.days_in_month(r_year, r_month, 1) as i32;
-        }
-        let iso_date = IsoDate {
-            day: r_day.try_into().unwrap(),
-            month: r_month,
-            year: r_year,
-        };
+        let epoch_day = IsoDate::new_unchecked(year, month, day).to_epoch_day()
+            + 7 * dur.weeks() as i64
+            + dur.days() as i64;
+        let iso_date = IsoDate::from_epoch_day(epoch_day);
         if need_constrain {
             FromYMDResult::OverflowConstrained(iso_date)
         } else {
             FromYMDResult::Normal(iso_date)
-        }*/
-        todo!()
+        }
     }
 }
@@ -0,0 +1,189 @@
+//! The `indian` national calendar
+//!
+//! Years are counted in the Saka era, and start on the March equinox of the ISO
+//! calendar, that is March 22nd, or March 21st in ISO leap years. Leap years follow
+//! the ISO calendar, and add a day to the first month.
+
+use super::*;
+
+pub(crate) struct IndianCalendar;
+
+/// Difference between the ISO year and the Saka year which starts in it
+const SAKA_OFFSET: i32 = 78;
+
+impl IndianCalendar {
+    fn is_leap(year: i32) -> bool {
+        IsoCalendar.in_leap_year(IsoDate::new_unchecked(year + SAKA_OFFSET, 1, 1))
+    }
+
+    fn days_in_month_of(year: i32, month: u32) -> u32 {
+        match month {
+            1 if Self::is_leap(year) => 31,
+            2..=6 => 31,
+            _ => 30,
+        }
+    }
+
+    /// Epoch day of the first day of the year
+    fn new_year(year: i32) -> i64 {
+        let day = if Self::is_leap(year) { 21 } else { 22 };
+        IsoDate::new_unchecked(year + SAKA_OFFSET, 3, day).to_epoch_day()
+    }
+
+    fn to_epoch_day(year: i32, month: u32, day: u32) -> i64 {
+        let days_before: u32 = (1..month).map(|m| Self::days_in_month_of(year, m)).sum();
+        Self::new_year(year) + days_before as i64 + day as i64 - 1
+    }
+
+    /// Returns the year, month and day of the given date
+    fn locate(iso_date: IsoDate) -> (i32, u32, u32) {
+        let epoch_day = iso_date.to_epoch_day();
+        let mut year = iso_date.year() - SAKA_OFFSET;
+        if epoch_day < Self::new_year(year) {
+            year -= 1;
+        }
+        let mut day = (epoch_day - Self::new_year(year)) as u32;
+        let mut month = 1;
+        while day >= Self::days_in_month_of(year, month) {
+            day -= Self::days_in_month_of(year, month);
+            month += 1;
+        }
+        (year, month, day + 1)
+    }
+}
+
+#[allow(unused)]
+impl CalendarProtocol for IndianCalendar {
+    fn id(&self) -> &str {
+        "indian"
+    }
+
+    fn era(&self, iso_date: IsoDate) -> Option<Era> {
+        let year = self.year(iso_date);
+        if year <= 0 {
+            return None;
+        }
+        Some(Era {
+            name: "shaka",
+            year: year as u32,
+        })
+    }
+
+    fn year(&self, iso_date: IsoDate) -> i32 {
+        Self::locate(iso_date).0
+    }
+
+    fn month(&self, iso_date: IsoDate) -> u32 {
+        Self::locate(iso_date).1
+    }
+
+    fn month_code(&self, iso_date: IsoDate) -> MonthCode {
+        MonthCode::new(self.month(iso_date) as u8, false)
+    }
+
+    fn day(&self, iso_date: IsoDate) -> u32 {
+        Self::locate(iso_date).2
+    }
+
+    fn day_of_week(&self, iso_date: IsoDate) -> u32 {
+        iso_date.to_icu_date().day_of_week() as u32
+    }
+
+    fn day_of_year(&self, iso_date: IsoDate) -> u32 {
+        let (year, _, _) = Self::locate(iso_date);
+        (iso_date.to_epoch_day() - Self::new_year(year)) as u32 + 1
+    }
+
+    fn week_of_year(&self, iso_date: IsoDate) -> u32 {
+        iso_week_of_year(iso_date)
+    }
+
+    fn days_in_week(&self, iso_date: IsoDate) -> u32 {
+        7
+    }
+
+    fn days_in_month(&self, iso_date: IsoDate) -> u32 {
+        let (year, month, _) = Self::locate(iso_date);
+        Self::days_in_month_of(year, month)
+    }
+
+    fn days_in_year(&self, iso_date: IsoDate) -> u32 {
+        if self.in_leap_year(iso_date) {
+            366
+        } else {
+            365
+        }
+    }
+
+    fn months_in_year(&self, iso_date: IsoDate) -> u32 {
+        12
+    }
+
+    fn in_leap_year(&self, iso_date: IsoDate) -> bool {
+        Self::is_leap(self.year(iso_date))
+    }
+
+    fn from_ymd(&self, year: i32, month: u32, day: u32) -> FromYMDResult {
+        let r_year = year.clamp(
+            IsoDate::MIN_YEAR - SAKA_OFFSET,
+            IsoDate::MAX_YEAR - SAKA_OFFSET - 1,
+        );
+        let r_month = month.clamp(1, 12);
+        let r_day = day.clamp(1, Self::days_in_month_of(r_year, r_month));
+        let iso_date = IsoDate::from_epoch_day(Self::to_epoch_day(r_year, r_month, r_day));
+        if (r_year, r_month, r_day) == (year, month, day) {
+            FromYMDResult::Normal(iso_date)
+        } else {
+            FromYMDResult::OverflowConstrained(iso_date)
+        }
+    }
+
+    fn year_from_era(&self, era: &str, era_year: u32) -> Option<i32> {
+        (era == "shaka").then_some(era_year as i32)
+    }
+
+    fn date_add(&self, iso_date: IsoDate, dur: NominalDuration) -> FromYMDResult {
+        let (year, month, mut day) = Self::locate(iso_date);
+        let (year, month) = balance_year_month(year + dur.years(), month, dur.months(), 12);
+        let mut need_constrain = false;
+        if day > Self::days_in_month_of(year, month) {
+            need_constrain = true;
+            day = Self::days_in_month_of(year, month);
+        }
+        let epoch_day =
+            Self::to_epoch_day(year, month, day) + 7 * dur.weeks() as i64 + dur.days() as i64;
+        let iso_date = IsoDate::from_epoch_day(epoch_day);
+        if need_constrain {
+            FromYMDResult::OverflowConstrained(iso_date)
+        } else {
+            FromYMDResult::Normal(iso_date)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Calendar, CalendarProtocol, IsoDate, PlainDate};
+
+    #[test]
+    fn saka_new_year() {
+        let date = IsoDate::new(2023, 3, 21).unwrap();
+        assert_eq!(Calendar::Indian.year(date), 1944);
+        assert_eq!(Calendar::Indian.month(date), 12);
+        assert_eq!(Calendar::Indian.day(date), 30);
+        assert_eq!(Calendar::Indian.week_of_year(date), 12);
+        let date = IsoDate::new(2024, 3, 21).unwrap();
+        assert_eq!(Calendar::Indian.year(date), 1946);
+        assert_eq!(Calendar::Indian.day(date), 1);
+        assert_eq!(Calendar::Indian.days_in_month(date), 31);
+        assert!(Calendar::Indian.in_leap_year(date));
+    }
+
+    #[test]
+    fn from_ymd() {
+        let date = PlainDate::from_ymd(1891, 10, 11, Calendar::Indian).constrain();
+        assert_eq!(date.iso_date().year(), 1970);
+        assert_eq!(date.iso_date().month(), 1);
+        assert_eq!(date.iso_date().day(), 1);
+    }
+}
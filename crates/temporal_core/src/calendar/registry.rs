@@ -0,0 +1,225 @@
+//! Registry of user defined calendars
+//!
+//! `Calendar` is `Copy` and small, so instead of holding a trait object, custom calendars
+//! are kept in a global list, and `Calendar::Custom` keeps an index into it. Entries of
+//! the list are set once and never removed, so it is read without locking.
+
+use alloc::boxed::Box;
+use core::{
+    hint,
+    sync::atomic::{self, AtomicBool},
+};
+
+use once_cell::race::OnceBox;
+
+use super::*;
+
+type Entry = Box<dyn CalendarProtocol + Send + Sync>;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY: OnceBox<Entry> = OnceBox::new();
+
+static REGISTRY: [OnceBox<Entry>; 256] = [EMPTY; 256];
+
+/// Set while a calendar is registered, so that checking for a duplicate id and adding the
+/// calendar happen together
+static REGISTERING: AtomicBool = AtomicBool::new(false);
+
+struct RegisteringGuard;
+
+impl RegisteringGuard {
+    fn acquire() -> Self {
+        while REGISTERING
+            .compare_exchange_weak(
+                false,
+                true,
+                atomic::Ordering::Acquire,
+                atomic::Ordering::Relaxed,
+            )
+            .is_err()
+        {
+            hint::spin_loop();
+        }
+        RegisteringGuard
+    }
+}
+
+impl Drop for RegisteringGuard {
+    fn drop(&mut self) {
+        REGISTERING.store(false, atomic::Ordering::Release);
+    }
+}
+
+fn registered() -> impl Iterator<Item = &'static Entry> {
+    REGISTRY.iter().map_while(OnceBox::get)
+}
+
+/// Handle of a calendar registered with [`Calendar::register`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CustomCalendar(u8);
+
+#[non_exhaustive]
+#[derive(Debug, PartialEq, Eq)]
+pub enum CalendarRegistrationError {
+    /// The id is not a valid calendar identifier, which is one or more groups of 3 to 8
+    /// lowercase ascii alphanumerics, separated by `-`
+    InvalidId(String),
+    /// There is already a builtin or registered calendar with this id
+    DuplicateId(String),
+    /// The maximum number of custom calendars (256) is already registered
+    TooManyCalendars,
+}
+
+fn is_valid_id(id: &str) -> bool {
+    id.split('-').all(|part| {
+        (3..=8).contains(&part.len())
+            && part
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+    })
+}
+
+impl CustomCalendar {
+    pub(super) fn get(self) -> &'static dyn CalendarProtocol {
+        &**REGISTRY[self.0 as usize].get().unwrap()
+    }
+
+    pub(super) fn find(id: &str) -> Option<Self> {
+        registered()
+            .position(|c| c.id() == id)
+            .map(|i| Self(i as u8))
+    }
+}
+
+impl Calendar {
+    /// Registers a user defined calendar, so it can be used as a `Calendar`, and parsed
+    /// from its id, for example in `[u-ca=acme-445]` annotations.
+    ///
+    /// The calendar is never deallocated, so this is intended to be called once per
+    /// calendar, at the start of the program.
+    pub fn register(
+        calendar: impl CalendarProtocol + Send + Sync + 'static,
+    ) -> Result<Calendar, CalendarRegistrationError> {
+        use CalendarRegistrationError::*;
+        let id = calendar.id().to_string();
+        if !is_valid_id(&id) {
+            return Err(InvalidId(id));
+        }
+        let _guard = RegisteringGuard::acquire();
+        if Calendar::builtin(&id).is_some() || registered().any(|c| c.id() == id) {
+            return Err(DuplicateId(id));
+        }
+        let index = registered().count();
+        let slot = REGISTRY.get(index).ok_or(TooManyCalendars)?;
+        // Only fails if the slot is set, which the guard prevents
+        let _ = slot.set(Box::new(Box::new(calendar)));
+        Ok(Calendar::Custom(CustomCalendar(index as u8)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        calendar::{CalendarRegistrationError, FromYMDResult},
+        Calendar, CalendarProtocol, Era, IsoDate, MonthCode, NominalDuration, PlainDate,
+    };
+
+    /// ISO calendar with years counted from 1000
+    struct Shifted(&'static str);
+
+    #[allow(unused)]
+    impl CalendarProtocol for Shifted {
+        fn id(&self) -> &str {
+            self.0
+        }
+        fn era(&self, iso_date: IsoDate) -> Option<Era> {
+            None
+        }
+        fn year(&self, iso_date: IsoDate) -> i32 {
+            iso_date.year() - 1000
+        }
+        fn month(&self, iso_date: IsoDate) -> u32 {
+            Calendar::Iso8601.month(iso_date)
+        }
+        fn month_code(&self, iso_date: IsoDate) -> MonthCode {
+            Calendar::Iso8601.month_code(iso_date)
+        }
+        fn day(&self, iso_date: IsoDate) -> u32 {
+            Calendar::Iso8601.day(iso_date)
+        }
+        fn day_of_week(&self, iso_date: IsoDate) -> u32 {
+            Calendar::Iso8601.day_of_week(iso_date)
+        }
+        fn day_of_year(&self, iso_date: IsoDate) -> u32 {
+            Calendar::Iso8601.day_of_year(iso_date)
+        }
+        fn week_of_year(&self, iso_date: IsoDate) -> u32 {
+            Calendar::Iso8601.week_of_year(iso_date)
+        }
+        fn days_in_week(&self, iso_date: IsoDate) -> u32 {
+            7
+        }
+        fn days_in_month(&self, iso_date: IsoDate) -> u32 {
+            Calendar::Iso8601.days_in_month(iso_date)
+        }
+        fn days_in_year(&self, iso_date: IsoDate) -> u32 {
+            Calendar::Iso8601.days_in_year(iso_date)
+        }
+        fn months_in_year(&self, iso_date: IsoDate) -> u32 {
+            12
+        }
+        fn in_leap_year(&self, iso_date: IsoDate) -> bool {
+            Calendar::Iso8601.in_leap_year(iso_date)
+        }
+        fn from_ymd(&self, year: i32, month: u32, day: u32) -> FromYMDResult {
+            Calendar::Iso8601.from_ymd(year + 1000, month, day)
+        }
+        fn date_add(&self, iso_date: IsoDate, dur: NominalDuration) -> FromYMDResult {
+            Calendar::Iso8601.date_add(iso_date, dur)
+        }
+    }
+
+    #[test]
+    fn register_and_parse() {
+        let calendar = Calendar::register(Shifted("shifted")).unwrap();
+        assert_eq!(calendar.id(), "shifted");
+        let date: PlainDate = "2022-02-02[u-ca=shifted]".parse().unwrap();
+        assert_eq!(date.year(), 1022);
+        let date = PlainDate::from_ymd(1022, 2, 2, calendar).constrain();
+        assert_eq!(date.iso_date().year(), 2022);
+    }
+
+    #[test]
+    fn register_errors() {
+        assert_eq!(
+            Calendar::register(Shifted("coptic")).unwrap_err(),
+            CalendarRegistrationError::DuplicateId("coptic".to_string()),
+        );
+        assert_eq!(
+            Calendar::register(Shifted("Acme_445")).unwrap_err(),
+            CalendarRegistrationError::InvalidId("Acme_445".to_string()),
+        );
+        Calendar::register(Shifted("acme-445")).unwrap();
+        assert!(Calendar::register(Shifted("acme-445")).is_err());
+    }
+
+    #[test]
+    fn date_add() {
+        let calendar = Calendar::register(Shifted("shifted-add")).unwrap();
+        let date = PlainDate::from_ymd(1024, 1, 31, calendar).constrain();
+        let add = |years, months, weeks, days| {
+            let dur = NominalDuration::from_date_units(years, months, weeks, days);
+            calendar.date_add(date.iso_date(), dur)
+        };
+        let FromYMDResult::OverflowConstrained(iso_date) = add(0, 1, 0, 1) else {
+            panic!("February has no 31st day");
+        };
+        let result = PlainDate::from_iso_date(iso_date, calendar);
+        assert_eq!((result.year(), result.month(), result.day()), (1024, 3, 1));
+        let FromYMDResult::Normal(iso_date) = add(-1, -10, -1, -3) else {
+            panic!("March has a 31st day");
+        };
+        let result = PlainDate::from_iso_date(iso_date, calendar);
+        assert_eq!((result.year(), result.month(), result.day()), (1022, 3, 21));
+    }
+}
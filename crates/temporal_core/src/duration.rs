@@ -1,6 +1,18 @@
-use std::time::{SystemTime, Duration, UNIX_EPOCH};
+use alloc::string::String;
+use core::{
+    fmt::{self, Display, Write},
+    ops::Add,
+    str::FromStr,
+    time::Duration,
+};
+#[cfg(feature = "std")]
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::iso;
+use crate::{
+    error::{ParseError, ParseErrorKind},
+    format::{round, Precision, RoundingMode, ToStringOptions},
+    iso::{self, Cursor},
+};
 
 #[derive(Default)]
 pub struct NominalDuration {
@@ -27,8 +39,19 @@ impl NominalDuration {
     }
 
     pub fn from_years(years: i32) -> Self {
+        Self::from_date_units(years, 0, 0, 0)
+    }
+
+    /// Creates a duration of the given date units, which must not have different signs
+    pub(crate) fn from_date_units(years: i32, months: i32, weeks: i32, days: i32) -> Self {
+        let units = [years, months, weeks, days];
+        debug_assert!(units.iter().all(|u| *u >= 0) || units.iter().all(|u| *u <= 0));
         Self {
-            years: years.abs().try_into().unwrap(),
+            is_negative: units.iter().any(|u| *u < 0),
+            years: years.unsigned_abs(),
+            months: months.unsigned_abs(),
+            weeks: weeks.unsigned_abs(),
+            days: days.unsigned_abs(),
             ..Self::default()
         }
     }
@@ -47,6 +70,51 @@ impl NominalDuration {
     }
 }
 
+/// Writes the duration like `-P1Y2M3W4DT5H6M7.008S`, with the sub-second units combined
+/// into the fraction of the seconds, or `PT0S` if it's zero
+impl Display for NominalDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let subsec = u64::from(self.milli_seconds) * 1_000_000
+            + u64::from(self.micro_seconds) * 1000
+            + u64::from(self.nano_seconds);
+        let seconds = u64::from(self.seconds) + subsec / 1_000_000_000;
+        let nanos = (subsec % 1_000_000_000) as u32;
+        if self.is_negative {
+            write!(f, "-")?;
+        }
+        write!(f, "P")?;
+        for (value, unit) in [
+            (self.years, 'Y'),
+            (self.months, 'M'),
+            (self.weeks, 'W'),
+            (self.days, 'D'),
+        ] {
+            if value != 0 {
+                write!(f, "{}{}", value, unit)?;
+            }
+        }
+        let is_date_zero = self.years == 0 && self.months == 0 && self.weeks == 0 && self.days == 0;
+        let is_time_zero = self.hours == 0 && self.minutes == 0 && seconds == 0 && nanos == 0;
+        if is_time_zero && !is_date_zero {
+            return Ok(());
+        }
+        write!(f, "T")?;
+        if self.hours != 0 {
+            write!(f, "{}H", self.hours)?;
+        }
+        if self.minutes != 0 {
+            write!(f, "{}M", self.minutes)?;
+        }
+        if seconds != 0 || nanos != 0 || is_time_zero {
+            write!(f, "{}", seconds)?;
+            Precision::Auto.write_fraction(f, nanos)?;
+            write!(f, "S")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct SignedDuration {
     secs: i64,
     nanos: u32, // Always 0 <= nanos < NANOS_PER_SEC
@@ -69,9 +137,12 @@ impl TryFrom<Duration> for SignedDuration {
 }
 
 impl SignedDuration {
+    /// Time since unix epoch, from the clock of the current thread
+    ///
+    /// See [`Now`](crate::Now) for changing the clock.
+    #[cfg(feature = "std")]
     pub fn now() -> Self {
-        let now = SystemTime::now();
-        Self::from_system_time_since_unix(now)
+        crate::Now::new().instant()
     }
 
     pub(crate) fn new_unchecked(secs: i64, nanos: u32) -> Self {
@@ -86,21 +157,66 @@ impl SignedDuration {
         }
     }
 
-    pub fn from_iso_string(s: &str) -> Option<Self> {
+    /// Adds a number of nanoseconds less than a day, like an offset
+    pub(crate) fn add_nanoseconds(self, nanos: i64) -> Self {
+        let secs = nanos.div_euclid(1_000_000_000);
+        let nanos = nanos.rem_euclid(1_000_000_000) as i32;
+        Self::new(self.secs + secs, self.nanos as i32 + nanos)
+    }
+
+    /// Rounds to a multiple of `increment` nanoseconds
+    pub(crate) fn round(self, increment: i128, mode: RoundingMode) -> Self {
+        let nanos = i128::from(self.secs) * 1_000_000_000 + i128::from(self.nanos);
+        let nanos = round(nanos, increment, mode);
+        Self::new_unchecked(
+            nanos.div_euclid(1_000_000_000) as i64,
+            nanos.rem_euclid(1_000_000_000) as u32,
+        )
+    }
+
+    /// Writes the duration in seconds like `Display`, with the precision and rounding of
+    /// `options`. [`SmallestUnit::Minute`](crate::SmallestUnit::Minute) rounds to whole
+    /// minutes, which are still written as seconds like `PT120S`.
+    pub fn to_string_with_options(&self, options: &ToStringOptions) -> String {
+        let mut s = String::new();
+        self.write(&mut s, Precision::new(options), options.rounding_mode)
+            .unwrap();
+        s
+    }
+
+    fn write(&self, f: &mut impl Write, precision: Precision, mode: RoundingMode) -> fmt::Result {
+        let rounded = self.round(precision.increment(), mode);
+        let (secs, nanos) = if rounded.secs < 0 {
+            write!(f, "-")?;
+            if rounded.nanos == 0 {
+                (rounded.secs.unsigned_abs(), 0)
+            } else {
+                (rounded.secs.unsigned_abs() - 1, 1_000_000_000 - rounded.nanos)
+            }
+        } else {
+            (rounded.secs as u64, rounded.nanos)
+        };
+        write!(f, "PT{}", secs)?;
+        precision.write_fraction(f, nanos)?;
+        write!(f, "S")
+    }
+
+    pub fn from_iso_string(s: &str) -> Result<Self, ParseError> {
         let i = iso::parse(s)?;
-        let offset_secs = match i.timezone_offset? {
+        let offset = i
+            .timezone_offset
+            .ok_or(ParseError::new(ParseErrorKind::MissingOffset, s.len()))?;
+        let offset_nanos = match offset {
             iso::IsoOffset::Z => 0,
-            iso::IsoOffset::Numeric(x) => x.to_seconds() as i64,
-        };
-        let time_secs = if let Some(x) = i.time {
-            x.to_second().into()
-        } else {
-            0
+            iso::IsoOffset::Numeric(x) => x.to_nanoseconds(),
         };
-        let secs = i.date.to_epoch_second() + time_secs + offset_secs;
-        Some(Self::new(secs, 0))
+        let time_nanos = i.time.map_or(0, |x| x.to_nanosecond());
+        Ok(Self::from_secs(i.date.to_epoch_second())
+            .add_nanoseconds(time_nanos)
+            .add_nanoseconds(-offset_nanos))
     }
 
+    #[cfg(feature = "std")]
     pub(crate) fn from_system_time_since_unix(s: SystemTime) -> Self {
         match s.duration_since(UNIX_EPOCH) {
             Ok(d) => SignedDuration {
@@ -109,10 +225,7 @@ impl SignedDuration {
             },
             Err(e) => {
                 let d = e.duration();
-                SignedDuration {
-                    secs: -(d.as_secs() as i64),
-                    nanos: d.subsec_nanos(),
-                }
+                Self::new(-(d.as_secs() as i64), -(d.subsec_nanos() as i32))
             }
         }
     }
@@ -129,3 +242,195 @@ impl SignedDuration {
         self.nanos
     }
 }
+
+/// Writes the duration in seconds, like `PT90.5S` or `-PT0.25S`, which parses back with
+/// `FromStr`
+impl Display for SignedDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, Precision::Auto, RoundingMode::Trunc)
+    }
+}
+
+/// Parses a duration like `PT90.5S`, `-PT0.25S` or `P1DT2H30M`, which is what `Display`
+/// writes, or a duration string with days, hours, minutes and seconds. Days are always
+/// 24 hours, and only seconds may have a fraction.
+impl FromStr for SignedDuration {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        /// Parses an integer of one or more digits
+        fn parse_integer(cur: &mut Cursor<'_>) -> Result<i128, ParseError> {
+            let start = cur.offset();
+            let mut r: i128 = 0;
+            while let Some(d) = cur.peek().and_then(|c| c.to_digit(10)) {
+                cur.advance();
+                r = r * 10 + i128::from(d);
+                if r > i128::from(i64::MAX) {
+                    return Err(ParseError::new(ParseErrorKind::OutOfRange, start));
+                }
+            }
+            Ok(r)
+        }
+        let eat_either = |cur: &mut Cursor<'_>, c: char| {
+            cur.eat(c.to_ascii_uppercase()) || cur.eat(c.to_ascii_lowercase())
+        };
+        let mut cur = Cursor::new(s);
+        let is_neg = match cur.peek().and_then(iso::parse_sign) {
+            Some(is_neg) => {
+                cur.advance();
+                is_neg
+            }
+            None => false,
+        };
+        if !eat_either(&mut cur, 'P') {
+            return Err(cur.unexpected("'P'"));
+        }
+        let mut nanos: i128 = 0;
+        let mut has_unit = false;
+        if cur.peek().is_some_and(|c| c.is_ascii_digit()) {
+            nanos += parse_integer(&mut cur)? * 86_400_000_000_000;
+            if !eat_either(&mut cur, 'D') {
+                return Err(cur.unexpected("'D'"));
+            }
+            has_unit = true;
+        }
+        if eat_either(&mut cur, 'T') {
+            let mut units = [('H', 3_600_000_000_000), ('M', 60_000_000_000)].iter();
+            loop {
+                if !cur.peek().is_some_and(|c| c.is_ascii_digit()) {
+                    if !has_unit || cur.peek().is_some() {
+                        return Err(cur.unexpected("a digit"));
+                    }
+                    break;
+                }
+                let value = parse_integer(&mut cur)?;
+                has_unit = true;
+                if matches!(cur.peek(), Some('.' | ',')) {
+                    cur.advance();
+                    let mut fraction = 0;
+                    let mut digits = 0;
+                    while let Some(d) = cur.peek().and_then(|c| c.to_digit(10)) {
+                        if digits == 9 {
+                            return Err(cur.unexpected("at most 9 fractional digits"));
+                        }
+                        cur.advance();
+                        fraction = fraction * 10 + i128::from(d);
+                        digits += 1;
+                    }
+                    if digits == 0 {
+                        return Err(cur.unexpected("a digit"));
+                    }
+                    if !eat_either(&mut cur, 'S') {
+                        return Err(cur.unexpected("'S'"));
+                    }
+                    nanos += value * 1_000_000_000 + fraction * 10_i128.pow(9 - digits);
+                    break;
+                }
+                if eat_either(&mut cur, 'S') {
+                    nanos += value * 1_000_000_000;
+                    break;
+                }
+                let unit = cur.peek().map(|c| c.to_ascii_uppercase());
+                match units.by_ref().find(|(c, _)| Some(*c) == unit) {
+                    Some((_, scale)) => nanos += value * scale,
+                    None => return Err(cur.unexpected("a later unit designator")),
+                }
+                cur.advance();
+            }
+        } else if !has_unit {
+            return Err(cur.unexpected("a digit or 'T'"));
+        }
+        cur.finish()?;
+        let nanos = if is_neg { -nanos } else { nanos };
+        let secs = i64::try_from(nanos.div_euclid(1_000_000_000))
+            .map_err(|_| ParseError::new(ParseErrorKind::OutOfRange, 0))?;
+        Ok(Self::new_unchecked(secs, nanos.rem_euclid(1_000_000_000) as u32))
+    }
+}
+
+impl Add for SignedDuration {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.secs + rhs.secs, (self.nanos + rhs.nanos) as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::{NominalDuration, SignedDuration};
+
+    #[test]
+    fn nominal_to_string() {
+        assert_eq!(NominalDuration::default().to_string(), "PT0S");
+        assert_eq!(NominalDuration::from_years(2).to_string(), "P2Y");
+        assert_eq!(NominalDuration::from_years(-2).to_string(), "-P2Y");
+        let d = NominalDuration {
+            is_negative: true,
+            years: 1,
+            months: 2,
+            weeks: 3,
+            days: 4,
+            hours: 5,
+            minutes: 6,
+            seconds: 7,
+            milli_seconds: 8,
+            ..NominalDuration::default()
+        };
+        assert_eq!(d.to_string(), "-P1Y2M3W4DT5H6M7.008S");
+        let d = NominalDuration {
+            milli_seconds: 1500,
+            nano_seconds: 20,
+            ..NominalDuration::default()
+        };
+        assert_eq!(d.to_string(), "PT1.50000002S");
+        let d = NominalDuration {
+            days: 1,
+            minutes: 30,
+            ..NominalDuration::default()
+        };
+        assert_eq!(d.to_string(), "P1DT30M");
+    }
+
+    #[test]
+    fn signed_duration_round_trip() {
+        for d in [
+            SignedDuration::from_secs(90),
+            SignedDuration::new(1, 500_000_000),
+            SignedDuration::new(0, -250_000_000),
+            SignedDuration::from_secs(-86400),
+            SignedDuration::new(-1, 1),
+            SignedDuration::from_secs(0),
+        ] {
+            assert_eq!(d.to_string().parse::<SignedDuration>().unwrap(), d);
+        }
+        let parse = |s: &str| s.parse::<SignedDuration>().unwrap();
+        assert_eq!(parse("-P1D"), SignedDuration::from_secs(-86400));
+        assert_eq!(parse("P1DT2H30M"), SignedDuration::from_secs(95400));
+        assert_eq!(parse("pt1m0,5s"), SignedDuration::new(60, 500_000_000));
+        assert_eq!(parse("\u{2212}PT1.000000001S"), SignedDuration::new(-2, 999_999_999));
+        for s in [
+            "", "P", "PT", "P1D2H", "PT1S2M", "PT1M1H", "PT1.5M", "PT1.S", "PT0.1234567891S",
+            "P1W", "PT1H ", "PT99999999999999999999S",
+        ] {
+            assert!(s.parse::<SignedDuration>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn from_iso_string() {
+        let parse = |s| SignedDuration::from_iso_string(s).unwrap();
+        assert_eq!(parse("1970-01-01T00:00Z"), SignedDuration::from_secs(0));
+        assert_eq!(parse("2020-01-01T04:30+04:30"), parse("2020-01-01T00:00Z"));
+        assert_eq!(
+            parse("1970-01-01T00:00:00.25-01:00"),
+            SignedDuration::new(3600, 250_000_000)
+        );
+        assert_eq!(
+            parse("1969-12-31T23:59:59.999999999Z"),
+            SignedDuration::new(-1, 999_999_999)
+        );
+    }
+}
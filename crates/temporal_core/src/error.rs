@@ -0,0 +1,204 @@
+//! Errors of parsing strings, shared by all the types
+
+use alloc::string::String;
+use core::fmt::Display;
+
+/// Why and where parsing a string failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    kind: ParseErrorKind,
+    offset: usize,
+    expected: Option<&'static str>,
+}
+
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The string ended before it was complete
+    UnexpectedEnd,
+    UnexpectedCharacter(char),
+    /// The year, month and day don't form a date, or it's out of the supported range
+    InvalidDate,
+    /// The hour, minute or second is out of range, like `24:00`
+    InvalidTime,
+    /// The year is `-000000`, which is not allowed as it's the same as `+000000`
+    NegativeZeroYear,
+    /// An annotation has an invalid key or value, like an uppercase key
+    InvalidAnnotation,
+    /// An annotation marked with `!` has a key which is not known
+    UnknownCriticalAnnotation(String),
+    /// There are several calendar annotations, and one of them is critical
+    ConflictingCalendars,
+    /// A time zone annotation comes after another annotation
+    MisplacedTimeZone,
+    MissingTimeZone,
+    MissingOffset,
+    UnknownTimeZone(String),
+    UnknownCalendar(String),
+    /// The local date and time happens more than once, or never, in the time zone
+    NonUniqueTime,
+    /// The offset doesn't match the offset of the time zone at that instant
+    WrongOffset,
+    /// The offset has a fraction of a second, given in nanoseconds, which is only
+    /// allowed by [`TimeZone::parse_allowing_sub_second`](crate::TimeZone::parse_allowing_sub_second)
+    SubSecondOffset(i64),
+    OutOfRange,
+}
+
+impl ParseError {
+    pub(crate) fn new(kind: ParseErrorKind, offset: usize) -> Self {
+        Self {
+            kind,
+            offset,
+            expected: None,
+        }
+    }
+
+    pub(crate) fn with_expected(self, expected: &'static str) -> Self {
+        Self {
+            expected: Some(expected),
+            ..self
+        }
+    }
+
+    /// Moves the error by `by` bytes, for a string parsed as part of a larger one
+    pub(crate) fn shifted(self, by: usize) -> Self {
+        Self {
+            offset: self.offset + by,
+            ..self
+        }
+    }
+
+    pub fn kind(&self) -> &ParseErrorKind {
+        &self.kind
+    }
+
+    /// Byte offset in the string where the error was found
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Description of what was expected at the offset, like `a digit`
+    pub fn expected(&self) -> Option<&'static str> {
+        self.expected
+    }
+}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        use ParseErrorKind::*;
+        match self {
+            UnexpectedEnd => write!(f, "unexpected end of string"),
+            UnexpectedCharacter(c) => write!(f, "unexpected character {:?}", c),
+            InvalidDate => write!(f, "invalid date"),
+            InvalidTime => write!(f, "invalid time"),
+            NegativeZeroYear => write!(f, "year -000000 is not allowed"),
+            InvalidAnnotation => write!(f, "invalid annotation"),
+            UnknownCriticalAnnotation(key) => write!(f, "unknown critical annotation {:?}", key),
+            ConflictingCalendars => write!(f, "several calendars with a critical one"),
+            MisplacedTimeZone => write!(f, "time zone annotation after other annotations"),
+            MissingTimeZone => write!(f, "missing time zone annotation"),
+            MissingOffset => write!(f, "missing offset"),
+            UnknownTimeZone(id) => write!(f, "unknown time zone {:?}", id),
+            UnknownCalendar(id) => write!(f, "unknown calendar {:?}", id),
+            NonUniqueTime => write!(f, "local time is ambiguous or skipped in the time zone"),
+            WrongOffset => write!(f, "offset doesn't match the time zone"),
+            SubSecondOffset(_) => write!(f, "offset with a fraction of a second"),
+            OutOfRange => write!(f, "value out of range"),
+        }
+    }
+}
+
+/// Writes a message like `unexpected character 'x' at byte 5, expected a digit`
+impl Display for ParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} at byte {}", self.kind, self.offset)?;
+        if let Some(expected) = self.expected {
+            write!(f, ", expected {}", expected)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use core::str::FromStr;
+
+    use crate::{
+        Calendar, MonthCode, ParseError, ParseErrorKind::*, PlainDate, SignedDuration,
+        ZonedDateTime,
+    };
+
+    fn plain_date(s: &str) -> ParseError {
+        PlainDate::from_str(s).err().unwrap()
+    }
+
+    fn zoned_date_time(s: &str) -> ParseError {
+        ZonedDateTime::from_str(s).err().unwrap()
+    }
+
+    #[test]
+    fn positions() {
+        let e = plain_date("2022-02-0x");
+        assert_eq!(
+            (e.kind(), e.offset(), e.expected()),
+            (&UnexpectedCharacter('x'), 9, Some("a digit"))
+        );
+        assert_eq!(
+            e.to_string(),
+            "unexpected character 'x' at byte 9, expected a digit"
+        );
+        let e = plain_date("2022-02-02T10:00[Asia/Tehran");
+        assert_eq!(
+            (e.kind(), e.offset(), e.expected()),
+            (&UnexpectedEnd, 28, Some("']'"))
+        );
+        let e = plain_date("2022-13-01");
+        assert_eq!((e.kind(), e.offset()), (&InvalidDate, 5));
+        assert_eq!(plain_date("2022-02-30").offset(), 8);
+        assert_eq!(plain_date("20220230").offset(), 6);
+        assert_eq!(plain_date("+275760-01-01").offset(), 0);
+        assert_eq!(plain_date("-000000-01-01").kind(), &NegativeZeroYear);
+        let e = plain_date("2022-02-02[u-ca=foo]");
+        assert_eq!((e.kind(), e.offset()), (&UnknownCalendar("foo".into()), 16));
+        assert_eq!(e.to_string(), "unknown calendar \"foo\" at byte 16");
+        let e = plain_date("2022-02-02[!u-ca=chinese][u-ca=dangi]");
+        assert_eq!((e.kind(), e.offset()), (&ConflictingCalendars, 25));
+        let e = zoned_date_time("2022-02-02T00:00Z");
+        assert_eq!((e.kind(), e.offset()), (&MissingTimeZone, 17));
+        let e = zoned_date_time("2022-03-13T02:30[America/New_York]");
+        assert_eq!((e.kind(), e.offset()), (&NonUniqueTime, 16));
+        let e = zoned_date_time("2022-11-06T01:30:00[America/New_York]");
+        assert_eq!((e.kind(), e.offset()), (&NonUniqueTime, 19));
+        let e = SignedDuration::from_iso_string("2022-02-02T00:00").unwrap_err();
+        assert_eq!((e.kind(), e.offset()), (&MissingOffset, 16));
+    }
+
+    #[test]
+    fn calendars() {
+        let e = Calendar::from_str("foo").unwrap_err();
+        assert_eq!((e.kind(), e.offset()), (&UnknownCalendar("foo".into()), 0));
+        let e = MonthCode::from_str("M00").unwrap_err();
+        assert_eq!((e.kind(), e.offset()), (&OutOfRange, 1));
+        let e = MonthCode::from_str("M1").unwrap_err();
+        assert_eq!((e.kind(), e.offset()), (&UnexpectedEnd, 2));
+        let e = MonthCode::from_str("M05X").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "unexpected character 'X' at byte 3, expected end of string"
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn std_error() {
+        let e: Box<dyn std::error::Error> = Box::new(plain_date("2022"));
+        assert_eq!(
+            e.to_string(),
+            "unexpected end of string at byte 4, expected a digit"
+        );
+    }
+}
@@ -0,0 +1,215 @@
+//! Options of writing ISO 8601 / RFC 9557 strings, like the options of `toString` in
+//! Temporal
+
+use core::fmt::{self, Write};
+
+/// Options of `to_string_with_options` on the temporal types. Each type only uses the
+/// options which apply to it, so a `PlainDate` ignores everything but `calendar_name`.
+///
+/// The default writes the same string as `Display`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ToStringOptions {
+    /// Number of digits after the decimal point of the seconds, at most 9. `None` writes as
+    /// many as needed, leaving out trailing zeros.
+    pub fractional_second_digits: Option<u8>,
+    /// Smallest unit written, which takes precedence over `fractional_second_digits`
+    pub smallest_unit: Option<SmallestUnit>,
+    /// How the value is rounded to the precision
+    pub rounding_mode: RoundingMode,
+    pub calendar_name: DisplayCalendar,
+    pub offset: DisplayOffset,
+    pub time_zone_name: DisplayTimeZone,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmallestUnit {
+    Minute,
+    Second,
+    Millisecond,
+    Microsecond,
+    Nanosecond,
+}
+
+/// Rounding modes of Temporal. `Floor` and `Ceil` round towards negative and positive
+/// infinity, `Trunc` and `Expand` towards and away from zero. The `Half` modes round to
+/// the nearest value, and only use the direction on ties.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RoundingMode {
+    Ceil,
+    Floor,
+    Expand,
+    #[default]
+    Trunc,
+    HalfCeil,
+    HalfFloor,
+    HalfExpand,
+    HalfTrunc,
+    HalfEven,
+}
+
+/// Whether the calendar annotation, like `[u-ca=coptic]`, is written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DisplayCalendar {
+    /// Only when the calendar is not `iso8601`
+    #[default]
+    Auto,
+    Always,
+    Never,
+    /// Always, marked as critical like `[!u-ca=coptic]`
+    Critical,
+}
+
+/// Whether the offset, like `+04:30`, is written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DisplayOffset {
+    #[default]
+    Auto,
+    Never,
+}
+
+/// Whether the time zone annotation, like `[Asia/Tehran]`, is written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DisplayTimeZone {
+    #[default]
+    Auto,
+    Never,
+    /// Marked as critical like `[!Asia/Tehran]`
+    Critical,
+}
+
+/// Precision of the written seconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Precision {
+    Minute,
+    /// Digits as needed, without trailing zeros
+    Auto,
+    Digits(u8),
+}
+
+impl Precision {
+    pub(crate) fn new(options: &ToStringOptions) -> Self {
+        match (options.smallest_unit, options.fractional_second_digits) {
+            (Some(SmallestUnit::Minute), _) => Self::Minute,
+            (Some(SmallestUnit::Second), _) => Self::Digits(0),
+            (Some(SmallestUnit::Millisecond), _) => Self::Digits(3),
+            (Some(SmallestUnit::Microsecond), _) => Self::Digits(6),
+            (Some(SmallestUnit::Nanosecond), _) => Self::Digits(9),
+            (None, Some(digits)) => Self::Digits(digits.min(9)),
+            (None, None) => Self::Auto,
+        }
+    }
+
+    /// Nanoseconds the value is rounded to
+    pub(crate) fn increment(self) -> i128 {
+        match self {
+            Self::Minute => 60_000_000_000,
+            Self::Auto => 1,
+            Self::Digits(digits) => 10_i128.pow(9 - u32::from(digits)),
+        }
+    }
+
+    /// Writes `.` and the fraction of a second for the precision, if any
+    pub(crate) fn write_fraction(self, f: &mut impl Write, nanos: u32) -> fmt::Result {
+        let digits = match self {
+            Self::Minute | Self::Digits(0) => return Ok(()),
+            Self::Auto if nanos == 0 => return Ok(()),
+            Self::Auto => {
+                let fraction = alloc::format!("{:09}", nanos);
+                return write!(f, ".{}", fraction.trim_end_matches('0'));
+            }
+            Self::Digits(digits) => u32::from(digits),
+        };
+        let fraction = nanos / 10_u32.pow(9 - digits);
+        write!(f, ".{:0width$}", fraction, width = digits as usize)
+    }
+
+    /// Writes a time of day like `04:30:00.5` from the nanoseconds since midnight
+    pub(crate) fn write_time(self, f: &mut impl Write, nanos: i64) -> fmt::Result {
+        let secs = nanos / 1_000_000_000;
+        write!(f, "{:02}:{:02}", secs / 3600, secs / 60 % 60)?;
+        if self != Self::Minute {
+            write!(f, ":{:02}", secs % 60)?;
+            self.write_fraction(f, (nanos % 1_000_000_000) as u32)?;
+        }
+        Ok(())
+    }
+}
+
+/// Rounds `value` to a multiple of `increment`
+pub(crate) fn round(value: i128, increment: i128, mode: RoundingMode) -> i128 {
+    use RoundingMode::*;
+    let rem = value.rem_euclid(increment);
+    if rem == 0 {
+        return value;
+    }
+    let lower = value - rem;
+    let upper = lower + increment;
+    let negative = value < 0;
+    let up = match mode {
+        Ceil => true,
+        Floor => false,
+        Expand => !negative,
+        Trunc => negative,
+        _ if rem * 2 != increment => rem * 2 > increment,
+        HalfCeil => true,
+        HalfFloor => false,
+        HalfExpand => !negative,
+        HalfTrunc => negative,
+        HalfEven => (lower / increment) % 2 != 0,
+    };
+    if up {
+        upper
+    } else {
+        lower
+    }
+}
+
+/// Writes a year with four digits, or six with a sign outside `0..=9999`
+pub(crate) fn write_year(f: &mut impl Write, year: i32) -> fmt::Result {
+    if (0..=9999).contains(&year) {
+        write!(f, "{:04}", year)
+    } else {
+        write!(f, "{:+07}", year)
+    }
+}
+
+/// Writes the calendar annotation of `id` according to `display`
+pub(crate) fn write_calendar(
+    f: &mut impl Write,
+    id: &str,
+    display: DisplayCalendar,
+) -> fmt::Result {
+    match display {
+        DisplayCalendar::Auto if id == "iso8601" => Ok(()),
+        DisplayCalendar::Auto | DisplayCalendar::Always => write!(f, "[u-ca={}]", id),
+        DisplayCalendar::Never => Ok(()),
+        DisplayCalendar::Critical => write!(f, "[!u-ca={}]", id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{round, RoundingMode::*};
+
+    #[test]
+    fn rounding_modes() {
+        let cases = [
+            (Ceil, [-10, -10, -10, 20, 20, 20]),
+            (Floor, [-20, -20, -20, 10, 10, 10]),
+            (Expand, [-20, -20, -20, 20, 20, 20]),
+            (Trunc, [-10, -10, -10, 10, 10, 10]),
+            (HalfCeil, [-10, -10, -20, 10, 20, 20]),
+            (HalfFloor, [-10, -20, -20, 10, 10, 20]),
+            (HalfExpand, [-10, -20, -20, 10, 20, 20]),
+            (HalfTrunc, [-10, -10, -20, 10, 10, 20]),
+            (HalfEven, [-10, -20, -20, 10, 20, 20]),
+        ];
+        for (mode, expected) in cases {
+            let rounded = [-11, -15, -19, 11, 15, 19].map(|x| round(x, 10, mode));
+            assert_eq!(rounded, expected, "{:?}", mode);
+        }
+        assert_eq!(round(25, 10, HalfEven), 20);
+        assert_eq!(round(-25, 10, HalfEven), -20);
+        assert_eq!(round(-30, 10, Expand), -30);
+    }
+}
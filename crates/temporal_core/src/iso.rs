@@ -1,8 +1,17 @@
-use std::{iter::Peekable, str::Chars};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Display;
 
 use icu_calendar::{Date, Iso};
 
-use crate::{calendar::IsoCalendar, CalendarProtocol};
+use crate::{
+    calendar::IsoCalendar,
+    error::{ParseError, ParseErrorKind},
+    format::{round, write_year, Precision, ToStringOptions},
+    CalendarProtocol,
+};
 
 /// Represents a valid iso date, with -271820 <= year <= 275759
 #[derive(Debug, Clone, Copy)]
@@ -28,23 +37,31 @@ impl IsoDate {
     }
 
     pub(crate) fn to_epoch_second(&self) -> i64 {
+        self.to_epoch_day() * 24 * 60 * 60
+    }
+
+    pub(crate) fn from_epoch_second(secs: i64) -> Self {
+        Self::from_epoch_day(secs.div_euclid(24 * 60 * 60))
+    }
+
+    /// Number of days since 1970-01-01
+    pub(crate) fn to_epoch_day(self) -> i64 {
         let mut days: i64 = (self.year - 1970) as i64 * 365i64;
         days += (self.year - 1969).div_euclid(4) as i64;
         days -= (self.year - 1901).div_euclid(100) as i64;
         days += (self.year - 1601).div_euclid(400) as i64;
-        days += IsoCalendar.day_of_year(*self) as i64;
-        days * 24 * 60 * 60
+        days += IsoCalendar.day_of_year(self) as i64 - 1;
+        days
     }
 
-    pub(crate) fn from_epoch_second(secs: i64) -> Self {
-        const SEC_PER_DAY: i64 = 86400;
+    pub(crate) fn from_epoch_day(days: i64) -> Self {
         const DAYS_PER_400Y: i64 = 365 * 400 + 97;
         const DAYS_PER_100Y: i64 = 365 * 100 + 24;
         const DAYS_PER_4Y: i64 = 365 * 4 + 1;
         // 2000-03-01 (mod 400 year, immediately after feb29
-        const LEAPOCH: i64 = 946684800 + 86400 * (31 + 29);
+        const LEAPOCH: i64 = 10957 + 31 + 29;
         const DAYS_IN_MONTH: [i64; 12] = [31, 30, 31, 30, 31, 31, 30, 31, 30, 31, 31, 29];
-        let mut days = (secs - LEAPOCH).div_euclid(SEC_PER_DAY);
+        let mut days = days - LEAPOCH;
         let qc_cycle = days.div_euclid(DAYS_PER_400Y);
         days = days.rem_euclid(DAYS_PER_400Y);
         let mut c_cycle = days.div_euclid(DAYS_PER_100Y);
@@ -62,7 +79,7 @@ impl IsoDate {
             rem_years -= 1;
         }
         days -= rem_years * 365;
-        let years = rem_years + 4 * q_cycle + 100 * c_cycle + 400 * qc_cycle;
+        let mut years = rem_years + 4 * q_cycle + 100 * c_cycle + 400 * qc_cycle;
         let mut months = 3;
         for m in DAYS_IN_MONTH {
             if days < m {
@@ -71,10 +88,14 @@ impl IsoDate {
             months += 1;
             days -= m;
         }
+        if months > 12 {
+            months -= 12;
+            years += 1;
+        }
         Self {
             year: (years + 2000) as i32,
             month: months as u8,
-            day: days as u8,
+            day: days as u8 + 1,
         }
     }
 
@@ -96,6 +117,14 @@ impl IsoDate {
     }
 }
 
+/// Writes the date like `2022-02-02`, or `+275760-09-13` for years outside `0..=9999`
+impl Display for IsoDate {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write_year(f, self.year)?;
+        write!(f, "-{:02}-{:02}", self.month, self.day)
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy)]
 pub struct IsoTime {
     pub hour: u8,
@@ -111,16 +140,52 @@ impl IsoTime {
         self.hour as i32 * 60 * 60 + self.minute as i32 * 60 + self.second as i32
     }
 
-    pub(crate) fn has_nanosecond(&self) -> bool {
-        self.millisecond != 0 || self.microsecond != 0 || self.nanosecond != 0
-    }
-
     pub(crate) fn to_nanosecond(&self) -> i64 {
         self.to_second() as i64 * 1000_000_000
             + self.millisecond as i64 * 1000_000
             + self.microsecond as i64 * 1000
             + self.nanosecond as i64
     }
+
+    /// Inverse of `to_nanosecond`, for nanoseconds in a day
+    pub(crate) fn from_nanosecond(nanos: i64) -> Self {
+        let secs = nanos / 1_000_000_000;
+        let nanos = nanos % 1_000_000_000;
+        IsoTime {
+            hour: (secs / 3600) as u8,
+            minute: (secs / 60 % 60) as u8,
+            second: (secs % 60) as u8,
+            millisecond: (nanos / 1_000_000) as u16,
+            microsecond: (nanos / 1000 % 1000) as u16,
+            nanosecond: (nanos % 1000) as u16,
+        }
+    }
+}
+
+impl IsoTime {
+    /// Writes the time like `Display`, with the precision and rounding of `options`.
+    /// Rounding past the end of the day wraps to midnight.
+    pub fn to_string_with_options(&self, options: &ToStringOptions) -> String {
+        let precision = Precision::new(options);
+        let nanos = round(
+            self.to_nanosecond().into(),
+            precision.increment(),
+            options.rounding_mode,
+        );
+        let mut s = String::new();
+        precision
+            .write_time(&mut s, (nanos % 86_400_000_000_000) as i64)
+            .unwrap();
+        s
+    }
+}
+
+/// Writes the time like `04:30:00`, with as many fractional digits as needed like
+/// `04:30:00.25`
+impl Display for IsoTime {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        Precision::Auto.write_time(f, self.to_nanosecond())
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -130,8 +195,8 @@ pub(crate) struct IsoNumericOffset {
 }
 
 impl IsoNumericOffset {
-    pub(crate) fn to_seconds(&self) -> i32 {
-        let x = self.time.to_second();
+    pub(crate) fn to_nanoseconds(self) -> i64 {
+        let x = self.time.to_nanosecond();
         if self.is_neg {
             -x
         } else {
@@ -146,6 +211,25 @@ pub(crate) enum IsoOffset {
     Numeric(IsoNumericOffset),
 }
 
+/// Annotation of an RFC 9557 string, like `[!u-ca=hebrew]`
+///
+/// Annotations with keys unknown to this library are kept when they aren't critical, so
+/// they can be written back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    /// Whether the annotation is marked with `!`, so it must not be ignored
+    pub critical: bool,
+    pub key: String,
+    pub value: String,
+}
+
+impl Display for Annotation {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let flag = if self.critical { "!" } else { "" };
+        write!(f, "[{}{}={}]", flag, self.key, self.value)
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct IsoParsed {
     pub(crate) date: IsoDate,
@@ -153,142 +237,302 @@ pub(crate) struct IsoParsed {
     pub(crate) timezone_offset: Option<IsoOffset>,
     pub(crate) timezone_name: Option<String>,
     pub(crate) calendar: Option<String>,
+    /// Annotations with unknown keys, in order
+    pub(crate) annotations: Vec<Annotation>,
+    /// Byte offsets of the offset, time zone name and calendar, for errors about them. If
+    /// there is no offset, this is where it would be, right after the date time.
+    pub(crate) timezone_offset_at: usize,
+    pub(crate) timezone_name_at: usize,
+    pub(crate) calendar_at: usize,
 }
 
-type It<'a> = Peekable<Chars<'a>>;
+/// Position in a string being parsed
+pub(crate) struct Cursor<'a> {
+    text: &'a str,
+    offset: usize,
+}
 
-fn parse_bracket(it: &mut It<'_>) -> Option<(String, bool)> {
-    if *it.peek()? != '[' {
-        return None;
+impl<'a> Cursor<'a> {
+    pub(crate) fn new(text: &'a str) -> Self {
+        Self { text, offset: 0 }
     }
-    it.next();
-    let mut r = String::new();
-    loop {
-        let c = it.next()?;
-        if c == ']' {
-            break;
+
+    pub(crate) fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub(crate) fn peek(&self) -> Option<char> {
+        self.text[self.offset..].chars().next()
+    }
+
+    pub(crate) fn advance(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.offset += c.len_utf8();
+        Some(c)
+    }
+
+    /// Skips the character if it's `c`
+    pub(crate) fn eat(&mut self, c: char) -> bool {
+        let found = self.peek() == Some(c);
+        if found {
+            self.advance();
         }
-        r.push(c);
+        found
     }
-    if let Some(cal) = r.strip_prefix("u-ca=") {
-        return Some((cal.to_string(), true));
+
+    pub(crate) fn expect(&mut self, c: char, expected: &'static str) -> Result<(), ParseError> {
+        if !self.eat(c) {
+            return Err(self.unexpected(expected));
+        }
+        Ok(())
     }
-    Some((r, false))
-}
 
-fn parse_two_digit(it: &mut It<'_>) -> Option<u8> {
-    let d1 = it.next()?;
-    let d2 = it.next()?;
-    if let Some(d1) = d1.to_digit(10) {
-        if let Some(d2) = d2.to_digit(10) {
-            return Some(d1 as u8 * 10 + d2 as u8);
+    /// Error for the character at the cursor, which is not what was expected
+    pub(crate) fn unexpected(&self, expected: &'static str) -> ParseError {
+        let kind = match self.peek() {
+            Some(c) => ParseErrorKind::UnexpectedCharacter(c),
+            None => ParseErrorKind::UnexpectedEnd,
+        };
+        ParseError::new(kind, self.offset).with_expected(expected)
+    }
+
+    /// Checks that the whole string is parsed
+    pub(crate) fn finish(&self) -> Result<(), ParseError> {
+        match self.peek() {
+            Some(_) => Err(self.unexpected("end of string")),
+            None => Ok(()),
         }
     }
-    None
 }
 
-fn parse_two_digit_colon(it: &mut It<'_>, colon_optional: bool) -> Option<Vec<u8>> {
-    let mut r = vec![parse_two_digit(it)?];
+/// Parses a bracketed annotation, returning the offset of its content, its critical flag
+/// and its content
+fn parse_bracket<'a>(cur: &mut Cursor<'a>) -> Result<(usize, bool, &'a str), ParseError> {
+    cur.expect('[', "'['")?;
+    let critical = cur.eat('!');
+    let start = cur.offset();
     loop {
-        if let Some(':') = it.peek() {
-            it.next();
-            r.push(parse_two_digit(it)?);
-        } else {
-            if !colon_optional {
-                break;
-            }
-            if it.peek().filter(|c| c.is_digit(10)).is_some() {
-                r.push(parse_two_digit(it)?);
-            } else {
-                break;
+        match cur.peek() {
+            Some(']') => break,
+            Some(_) => {
+                cur.advance();
             }
+            None => return Err(cur.unexpected("']'")),
         }
     }
-    Some(r)
+    let content = &cur.text[start..cur.offset()];
+    cur.advance();
+    Ok((start, critical, content))
 }
 
-fn parse_root(it: &mut It<'_>) -> Option<IsoParsed> {
-    let date = parse_date(it)?;
-    let mut time = None;
-    let mut timezone_name = None;
-    let mut timezone_offset = None;
-    let mut calendar = None;
-    loop {
-        let c = match it.peek() {
-            Some(c) => *c,
-            None => {
-                return Some(IsoParsed {
-                    date,
-                    time,
-                    calendar,
-                    timezone_name,
-                    timezone_offset,
-                })
+/// Whether the text is a time zone id like `America/New_York`. Offsets like `+03:30` are
+/// accepted as is, and left to be checked when parsed as a time zone.
+fn is_time_zone_id(s: &str) -> bool {
+    if s.starts_with(|c| parse_sign(c).is_some()) {
+        return true;
+    }
+    s.split('/').all(|part| {
+        part.starts_with(|c: char| c.is_ascii_alphabetic() || c == '.' || c == '_')
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '+' | '-'))
+    })
+}
+
+/// Whether the text is an annotation key, which is lowercase as uppercase keys are reserved
+fn is_annotation_key(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+        && s
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
+
+fn is_annotation_value(s: &str) -> bool {
+    s.split('-')
+        .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// Parses the annotations ending a string, which are a time zone optionally, then any
+/// number of `key=value` pairs. Critical annotations with unknown keys are rejected. Only
+/// the first calendar is used, and several calendars are rejected if any is critical.
+fn parse_annotations(cur: &mut Cursor<'_>, parsed: &mut IsoParsed) -> Result<(), ParseError> {
+    use ParseErrorKind::*;
+    let mut first = true;
+    let mut calendar_critical = false;
+    while cur.peek().is_some() {
+        let start = cur.offset();
+        let (at, critical, content) = parse_bracket(cur)?;
+        let Some((key, value)) = content.split_once('=') else {
+            if !first {
+                return Err(ParseError::new(MisplacedTimeZone, start));
+            }
+            if !is_time_zone_id(content) {
+                return Err(ParseError::new(InvalidAnnotation, at).with_expected("a time zone id"));
             }
+            parsed.timezone_name = Some(content.to_string());
+            parsed.timezone_name_at = at;
+            first = false;
+            continue;
         };
-        match c {
-            'T' => {
-                it.next();
-                time = Some(parse_time(it, false)?);
+        first = false;
+        if !is_annotation_key(key) {
+            return Err(ParseError::new(InvalidAnnotation, at).with_expected("a lowercase key"));
+        }
+        if !is_annotation_value(value) {
+            return Err(ParseError::new(InvalidAnnotation, at + key.len() + 1)
+                .with_expected("alphanumeric parts separated by '-'"));
+        }
+        match key {
+            "u-ca" => {
+                if parsed.calendar.is_some() && (critical || calendar_critical) {
+                    return Err(ParseError::new(ConflictingCalendars, start));
+                }
+                calendar_critical |= critical;
+                if parsed.calendar.is_none() {
+                    parsed.calendar = Some(value.to_string());
+                    parsed.calendar_at = at + key.len() + 1;
+                }
+            }
+            _ if critical => {
+                return Err(ParseError::new(UnknownCriticalAnnotation(key.to_string()), at));
             }
-            'Z' => {
-                it.next();
-                timezone_offset = Some(IsoOffset::Z);
+            _ => parsed.annotations.push(Annotation {
+                critical,
+                key: key.to_string(),
+                value: value.to_string(),
+            }),
+        }
+    }
+    Ok(())
+}
+
+fn parse_digit(cur: &mut Cursor<'_>) -> Result<u32, ParseError> {
+    match cur.peek().and_then(|c| c.to_digit(10)) {
+        Some(d) => {
+            cur.advance();
+            Ok(d)
+        }
+        None => Err(cur.unexpected("a digit")),
+    }
+}
+
+pub(crate) fn parse_two_digit(cur: &mut Cursor<'_>) -> Result<u8, ParseError> {
+    Ok((parse_digit(cur)? * 10 + parse_digit(cur)?) as u8)
+}
+
+/// Parses up to three two digit numbers, separated by colons like `10:15:00` in extended
+/// format, or not separated like `101500` in basic format, without mixing the two. Each
+/// number is checked to be at most its maximum in `max`.
+fn parse_two_digit_colon(cur: &mut Cursor<'_>, max: [u8; 3]) -> Result<Vec<u8>, ParseError> {
+    let mut r = Vec::new();
+    let mut extended = false;
+    loop {
+        let start = cur.offset();
+        let x = parse_two_digit(cur)?;
+        if x > max[r.len()] {
+            return Err(ParseError::new(ParseErrorKind::InvalidTime, start));
+        }
+        r.push(x);
+        if r.len() == 3 {
+            break;
+        }
+        if r.len() == 1 {
+            extended = cur.peek() == Some(':');
+        }
+        match cur.peek() {
+            Some(':') if extended => {
+                cur.advance();
             }
-            c if parse_sign(c).is_some() => {
-                timezone_offset = Some(IsoOffset::Numeric(parse_numeric_timezone(it)?));
+            Some(c) if !extended && c.is_ascii_digit() => (),
+            _ => break,
+        }
+    }
+    Ok(r)
+}
+
+fn parse_root(cur: &mut Cursor<'_>) -> Result<IsoParsed, ParseError> {
+    let date = parse_date(cur)?;
+    let mut parsed = IsoParsed {
+        date,
+        time: None,
+        timezone_offset: None,
+        timezone_name: None,
+        calendar: None,
+        annotations: Vec::new(),
+        timezone_offset_at: cur.offset(),
+        timezone_name_at: 0,
+        calendar_at: 0,
+    };
+    if let Some('T' | 't' | ' ') = cur.peek() {
+        cur.advance();
+        parsed.time = Some(parse_time(cur, true)?);
+        parsed.timezone_offset_at = cur.offset();
+        match cur.peek() {
+            Some('Z' | 'z') => {
+                cur.advance();
+                parsed.timezone_offset = Some(IsoOffset::Z);
             }
-            '[' => {
-                let (content, is_cal) = parse_bracket(it)?;
-                if is_cal {
-                    calendar = Some(content);
-                } else {
-                    timezone_name = Some(content);
-                }
+            Some(c) if parse_sign(c).is_some() => {
+                parsed.timezone_offset = Some(IsoOffset::Numeric(parse_numeric_timezone(cur)?));
             }
-            _ => return None,
+            _ => (),
         }
     }
+    parse_annotations(cur, &mut parsed)?;
+    Ok(parsed)
 }
 
-fn parse_numeric_timezone(it: &mut Peekable<Chars>) -> Option<IsoNumericOffset> {
-    let c = it.next()?;
-    let is_neg = parse_sign(c)?;
-    let time = parse_time(it, true)?;
-    Some(IsoNumericOffset { is_neg, time })
+pub(crate) fn parse_numeric_timezone(
+    cur: &mut Cursor<'_>,
+) -> Result<IsoNumericOffset, ParseError> {
+    let is_neg = match cur.peek().and_then(parse_sign) {
+        Some(is_neg) => is_neg,
+        None => return Err(cur.unexpected("'+' or '-'")),
+    };
+    cur.advance();
+    let time = parse_time(cur, false)?;
+    Ok(IsoNumericOffset { is_neg, time })
 }
 
-pub(crate) fn parse_time(it: &mut It<'_>, colon_optional: bool) -> Option<IsoTime> {
-    fn parse3(it: &mut It<'_>) -> u16 {
+/// Parses a time like `10:15:30.5`. With `leap_second`, a second of 60 is accepted and
+/// clamped to 59, as Temporal does, which is not allowed in offsets.
+fn parse_time(cur: &mut Cursor<'_>, leap_second: bool) -> Result<IsoTime, ParseError> {
+    fn parse3(cur: &mut Cursor<'_>) -> u16 {
         let mut r = 0;
         for _ in 0..3 {
             r *= 10;
-            if let Some(c) = it.peek() {
-                if let Some(d) = c.to_digit(10) {
-                    it.next();
-                    r += d as u16;
-                }
+            if let Some(d) = cur.peek().and_then(|c| c.to_digit(10)) {
+                cur.advance();
+                r += d as u16;
             }
         }
         r
     }
+    let max_second = if leap_second { 60 } else { 59 };
     let (hour, minute, second, has_sec) =
-        match parse_two_digit_colon(it, colon_optional)?.as_slice() {
+        match parse_two_digit_colon(cur, [23, 59, max_second])?.as_slice() {
             [h] => (*h, 0, 0, false),
             [h, m] => (*h, *m, 0, false),
-            [h, m, s] => (*h, *m, *s, true),
-            _ => return None,
+            [h, m, s] => (*h, *m, (*s).min(59), true),
+            _ => unreachable!(),
         };
-    let (millisecond, microsecond, nanosecond) = if has_sec && it.peek() == Some(&'.') {
-        it.next();
-        let a = parse3(it);
-        let b = parse3(it);
-        let c = parse3(it);
+    let has_fraction = has_sec && matches!(cur.peek(), Some('.' | ','));
+    let (millisecond, microsecond, nanosecond) = if has_fraction {
+        cur.advance();
+        if !cur.peek().is_some_and(|c| c.is_ascii_digit()) {
+            return Err(cur.unexpected("a digit"));
+        }
+        let a = parse3(cur);
+        let b = parse3(cur);
+        let c = parse3(cur);
+        if cur.peek().is_some_and(|c| c.is_ascii_digit()) {
+            return Err(cur.unexpected("at most 9 fractional digits"));
+        }
         (a, b, c)
     } else {
         (0, 0, 0)
     };
-    Some(IsoTime {
+    Ok(IsoTime {
         hour,
         minute,
         second,
@@ -298,41 +542,52 @@ pub(crate) fn parse_time(it: &mut It<'_>, colon_optional: bool) -> Option<IsoTim
     })
 }
 
-fn parse_date(it: &mut Peekable<Chars>) -> Option<IsoDate> {
-    let year = parse_num(it, 4)? as i32;
-    eat_char(it, '-')?;
-    let month = parse_num(it, 2)? as u8;
-    eat_char(it, '-')?;
-    let day = parse_num(it, 2)? as u8;
-    IsoDate::new(year, month, day)
-}
-
-fn eat_char(it: &mut It<'_>, c: char) -> Option<()> {
-    if it.peek()? != &c {
-        return None;
+/// Parses a date like `2022-02-02`, or `20220202` in basic format. Years out of 0000 to
+/// 9999 have a sign and six digits, like `-001000-02-02`.
+fn parse_date(cur: &mut Cursor<'_>) -> Result<IsoDate, ParseError> {
+    let start = cur.offset();
+    let year = match cur.peek().and_then(parse_sign) {
+        Some(is_neg) => {
+            cur.advance();
+            let x = parse_num(cur, 6)? as i32;
+            // There is no negative zero year
+            if is_neg && x == 0 {
+                return Err(ParseError::new(ParseErrorKind::NegativeZeroYear, start));
+            }
+            if is_neg {
+                -x
+            } else {
+                x
+            }
+        }
+        None => parse_num(cur, 4)? as i32,
+    };
+    let extended = cur.eat('-');
+    let month_at = cur.offset();
+    let month = parse_num(cur, 2)? as u8;
+    if extended {
+        cur.expect('-', "'-'")?;
     }
-    it.next();
-    Some(())
+    let day_at = cur.offset();
+    let day = parse_num(cur, 2)? as u8;
+    // Point at the field which makes the date invalid
+    let invalid_at = if !(IsoDate::MIN_YEAR..=IsoDate::MAX_YEAR).contains(&year) {
+        start
+    } else if !(1..=12).contains(&month) {
+        month_at
+    } else {
+        day_at
+    };
+    IsoDate::new(year, month, day).ok_or(ParseError::new(ParseErrorKind::InvalidDate, invalid_at))
 }
 
-fn parse_num(it: &mut It<'_>, mut cnt: usize) -> Option<u32> {
-    if !it.peek()?.is_digit(10) {
-        return None;
-    }
+/// Parses a number of exactly `cnt` digits
+fn parse_num(cur: &mut Cursor<'_>, cnt: usize) -> Result<u32, ParseError> {
     let mut result = 0;
-    while let Some(x) = it.peek().and_then(|x| x.to_digit(10)) {
-        if cnt == 0 {
-            return Some(result);
-        }
-        result *= 10;
-        result += x;
-        it.next();
-        cnt -= 1;
+    for _ in 0..cnt {
+        result = result * 10 + parse_digit(cur)?;
     }
-    if cnt > 0 {
-        return None;
-    }
-    Some(result)
+    Ok(result)
 }
 
 /// returns Some(true) for - and u2212
@@ -344,7 +599,119 @@ pub(crate) fn parse_sign(c: char) -> Option<bool> {
     }
 }
 
-pub(crate) fn parse(text: &str) -> Option<IsoParsed> {
-    let mut chars = text.chars().peekable();
-    parse_root(&mut chars)
+pub(crate) fn parse(text: &str) -> Result<IsoParsed, ParseError> {
+    parse_root(&mut Cursor::new(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Annotation};
+    use crate::{ParseError, ParseErrorKind::*};
+
+    fn error(s: &str) -> ParseError {
+        parse(s).unwrap_err()
+    }
+
+    #[test]
+    fn extended_years() {
+        let i = parse("-271820-04-20").unwrap();
+        assert_eq!((i.date.year(), i.date.month(), i.date.day()), (-271820, 4, 20));
+        assert_eq!(parse("+275759-09-13").unwrap().date.year(), 275759);
+        assert_eq!(parse("\u{2212}000001-01-01").unwrap().date.year(), -1);
+        assert_eq!(parse("+000000-01-01").unwrap().date.year(), 0);
+        for s in ["-000000-01-01", "+2022-01-01", "+0020221-01-01", "+275760-01-01"] {
+            assert!(parse(s).is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn basic_format() {
+        let i = parse("20220202T101530,5Z").unwrap();
+        assert_eq!((i.date.year(), i.date.month(), i.date.day()), (2022, 2, 2));
+        let time = i.time.unwrap();
+        assert_eq!((time.hour, time.minute, time.second), (10, 15, 30));
+        assert_eq!(time.millisecond, 500);
+        let i = parse("2022-02-02 10:15:30.123456789+0330").unwrap();
+        assert_eq!(i.time.unwrap().nanosecond, 789);
+        let offset = match i.timezone_offset.unwrap() {
+            super::IsoOffset::Numeric(x) => x.to_nanoseconds(),
+            super::IsoOffset::Z => 0,
+        };
+        assert_eq!(offset, 12600 * 1_000_000_000);
+        assert!(parse("+0020220202t1015z").is_ok());
+        for s in [
+            "2022-0202",
+            "202202-02",
+            "2022-02-02T10:1530",
+            "2022-02-02T1015:30",
+            "2022-02-02T10:15:30.",
+            "2022-02-02 ",
+            "2022-02-02T10:15+03:3000",
+        ] {
+            assert!(parse(s).is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn time_fields() {
+        let e = error("2022-02-02T24:00");
+        assert_eq!((e.kind(), e.offset()), (&InvalidTime, 11));
+        assert_eq!(error("2022-02-02T23:60").offset(), 14);
+        assert_eq!(error("2022-02-02T235961").offset(), 15);
+        let time = parse("2016-12-31T23:59:60Z").unwrap().time.unwrap();
+        assert_eq!((time.hour, time.minute, time.second), (23, 59, 59));
+        // Offsets don't have leap seconds
+        let e = error("2022-02-02T00:00+00:00:60");
+        assert_eq!((e.kind(), e.offset()), (&InvalidTime, 23));
+        assert_eq!(error("2022-02-02T00:00-24").kind(), &InvalidTime);
+        assert_eq!(parse("2022-02-02T00:00:00.123456789").unwrap().time.unwrap().nanosecond, 789);
+        let e = error("2022-02-02T00:00:00.1234567891");
+        assert_eq!((e.kind(), e.offset()), (&UnexpectedCharacter('1'), 29));
+        assert_eq!(e.expected(), Some("at most 9 fractional digits"));
+    }
+
+    #[test]
+    fn annotations() {
+        let i = parse("2022-09-01T00:00+04:30[!Asia/Tehran][u-ca=persian][foo=bar-baz]").unwrap();
+        assert_eq!(i.timezone_name.as_deref(), Some("Asia/Tehran"));
+        assert_eq!(i.calendar.as_deref(), Some("persian"));
+        assert_eq!(
+            i.annotations,
+            [Annotation {
+                critical: false,
+                key: "foo".into(),
+                value: "bar-baz".into(),
+            }]
+        );
+        assert_eq!(i.annotations[0].to_string(), "[foo=bar-baz]");
+        let i = parse("2022-09-01[u-ca=chinese][u-ca=dangi]").unwrap();
+        assert_eq!(i.calendar.as_deref(), Some("chinese"));
+        let i = parse("2022-09-01T00:00z[+04:30]").unwrap();
+        assert_eq!(i.timezone_name.as_deref(), Some("+04:30"));
+    }
+
+    #[test]
+    fn invalid_annotations() {
+        for s in [
+            // Unknown critical key
+            "2022-09-01[!foo=bar]",
+            // Several calendars, one of them critical
+            "2022-09-01[!u-ca=chinese][u-ca=dangi]",
+            "2022-09-01[u-ca=chinese][!u-ca=dangi]",
+            // Time zone after another annotation, or twice
+            "2022-09-01[u-ca=chinese][Asia/Tehran]",
+            "2022-09-01T00:00[Asia/Tehran][Europe/Berlin]",
+            // Uppercase keys are reserved
+            "2022-09-01[U-CA=chinese]",
+            "2022-09-01[foo=]",
+            "2022-09-01[foo=bar--baz]",
+            "2022-09-01[Asia//Tehran]",
+            "2022-09-01[u-ca=chinese",
+            // Offset after the annotations, or without time
+            "2022-09-01T00:00[Asia/Tehran]Z",
+            "2022-09-01+04:30",
+        ] {
+            assert!(parse(s).is_err(), "{}", s);
+        }
+    }
 }
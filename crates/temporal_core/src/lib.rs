@@ -1,24 +1,44 @@
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
+
+extern crate alloc;
+
 mod calendar;
 mod duration;
+mod error;
+mod format;
 mod iso;
+#[cfg(feature = "std")]
+mod now;
 mod plain;
 mod timezone;
 mod zoned;
 
-pub use calendar::{Calendar, CalendarProtocol, Era};
+pub use calendar::{
+    Calendar, CalendarProtocol, CalendarRegistrationError, CustomCalendar, DateFields,
+    DateFieldsError, Era, FromYMDResult, MonthCode,
+};
+#[cfg(feature = "std")]
+pub use now::{Clock, ManualClock, Now, SystemClock, ThreadClock};
 pub use plain::PlainDate;
 pub use zoned::ZonedDateTime;
-pub use timezone::{TimeZone, TimeZoneProtocol};
+pub use timezone::{
+    OffsetInfo, TimeZone, TimeZoneProtocol, TimeZoneTransition, TzifError, ZoneLocation,
+};
 pub use duration::{SignedDuration, NominalDuration};
-pub use iso::{IsoDate, IsoTime};
+pub use error::{ParseError, ParseErrorKind};
+pub use format::{
+    DisplayCalendar, DisplayOffset, DisplayTimeZone, RoundingMode, SmallestUnit, ToStringOptions,
+};
+pub use iso::{Annotation, IsoDate, IsoTime};
 
 #[cfg(test)]
 mod tests {
-    use std::str::FromStr;
+    use std::{cmp::Ordering, str::FromStr};
 
     use crate::{
-        zoned::{ZonedDateTime, ZonedDateTimeParseError},
-        Calendar, PlainDate,
+        zoned::ZonedDateTime, Calendar, DateFields, DateFieldsError, DisplayCalendar,
+        DisplayOffset, DisplayTimeZone, IsoTime, ParseErrorKind, PlainDate, RoundingMode,
+        SignedDuration, SmallestUnit, TimeZone, ToStringOptions,
     };
 
     #[test]
@@ -48,7 +68,225 @@ mod tests {
         assert_eq!(result3.hour(), 0);
         assert!(matches!(
             ZonedDateTime::from_str("2022-09-01T00:00+04[+04:30]"),
-            Err(ZonedDateTimeParseError::WrongOffset),
+            Err(e) if e.kind() == &ParseErrorKind::WrongOffset && e.offset() == 16,
+        ));
+    }
+
+    #[test]
+    fn zoned_extended_year() {
+        let result: ZonedDateTime = "-001000-01-01T00:00Z[UTC]".parse().unwrap();
+        assert_eq!(result.year(), -1000);
+        assert_eq!(result.to_string(), "-001000-01-01T00:00:00+00:00[UTC]");
+        let result: ZonedDateTime = "+0120000101T0000+0330[Asia/Tehran]".parse().unwrap();
+        assert_eq!(result.to_string(), "+012000-01-01T00:00:00+03:30[Asia/Tehran]");
+    }
+
+    #[test]
+    fn zoned_to_string() {
+        let s = "2022-09-01T04:30:00.123456789+04:30[Asia/Tehran][u-ca=coptic]";
+        let result: ZonedDateTime = s.parse().unwrap();
+        assert_eq!(result.to_string(), s);
+        let options = |options: ToStringOptions| result.to_string_with_options(&options);
+        assert_eq!(
+            options(ToStringOptions {
+                smallest_unit: Some(SmallestUnit::Minute),
+                calendar_name: DisplayCalendar::Never,
+                ..Default::default()
+            }),
+            "2022-09-01T04:30+04:30[Asia/Tehran]"
+        );
+        assert_eq!(
+            options(ToStringOptions {
+                fractional_second_digits: Some(4),
+                rounding_mode: RoundingMode::HalfExpand,
+                offset: DisplayOffset::Never,
+                time_zone_name: DisplayTimeZone::Critical,
+                calendar_name: DisplayCalendar::Critical,
+                ..Default::default()
+            }),
+            "2022-09-01T04:30:00.1235[!Asia/Tehran][!u-ca=coptic]"
+        );
+        assert_eq!(
+            options(ToStringOptions {
+                fractional_second_digits: Some(0),
+                smallest_unit: Some(SmallestUnit::Millisecond),
+                time_zone_name: DisplayTimeZone::Never,
+                ..Default::default()
+            }),
+            "2022-09-01T04:30:00.123+04:30[u-ca=coptic]"
+        );
+        let result: ZonedDateTime = "1999-12-31T23:59:59.5Z[UTC]".parse().unwrap();
+        let options = ToStringOptions {
+            smallest_unit: Some(SmallestUnit::Second),
+            rounding_mode: RoundingMode::HalfEven,
+            calendar_name: DisplayCalendar::Always,
+            ..Default::default()
+        };
+        assert_eq!(
+            result.to_string_with_options(&options),
+            "2000-01-01T00:00:00+00:00[UTC][u-ca=iso8601]"
+        );
+    }
+
+    #[test]
+    fn plain_to_string() {
+        for s in ["2022-02-02", "2022-02-02[u-ca=coptic]", "+012345-01-01", "-000001-12-31"] {
+            assert_eq!(PlainDate::from_str(s).unwrap().to_string(), s);
+        }
+        let date = PlainDate::from_str("2022-02-02").unwrap();
+        let options = ToStringOptions {
+            calendar_name: DisplayCalendar::Always,
+            ..Default::default()
+        };
+        assert_eq!(date.to_string_with_options(&options), "2022-02-02[u-ca=iso8601]");
+        assert_eq!(date.with_calendar(Calendar::Coptic).to_string(), "2022-02-02[u-ca=coptic]");
+        assert_eq!(Calendar::Coptic.to_string(), "coptic");
+        assert_eq!(date.iso_date().to_string(), "2022-02-02");
+        let time = IsoTime {
+            hour: 23,
+            minute: 59,
+            second: 59,
+            millisecond: 999,
+            ..Default::default()
+        };
+        assert_eq!(time.to_string(), "23:59:59.999");
+        let options = ToStringOptions {
+            smallest_unit: Some(SmallestUnit::Second),
+            rounding_mode: RoundingMode::Ceil,
+            ..Default::default()
+        };
+        assert_eq!(time.to_string_with_options(&options), "00:00:00");
+    }
+
+    #[test]
+    fn signed_duration_to_string() {
+        assert_eq!(SignedDuration::new(0, 0).to_string(), "PT0S");
+        assert_eq!(SignedDuration::from_secs(90).to_string(), "PT90S");
+        assert_eq!(SignedDuration::new(-2, 500_000_000).to_string(), "-PT1.5S");
+        assert_eq!(SignedDuration::new(-1, 0).to_string(), "-PT1S");
+        let d = SignedDuration::new(1, 123_456_789);
+        let options = |fractional_second_digits, rounding_mode| ToStringOptions {
+            fractional_second_digits,
+            rounding_mode,
+            ..Default::default()
+        };
+        assert_eq!(d.to_string_with_options(&options(Some(3), RoundingMode::Trunc)), "PT1.123S");
+        assert_eq!(d.to_string_with_options(&options(Some(3), RoundingMode::Ceil)), "PT1.124S");
+        assert_eq!(
+            d.to_string_with_options(&options(Some(9), RoundingMode::Ceil)),
+            "PT1.123456789S"
+        );
+        let d = SignedDuration::new(-2, 876_543_211);
+        assert_eq!(d.to_string_with_options(&options(Some(2), RoundingMode::Floor)), "-PT1.13S");
+        let options = ToStringOptions {
+            smallest_unit: Some(SmallestUnit::Minute),
+            rounding_mode: RoundingMode::HalfExpand,
+            ..Default::default()
+        };
+        assert_eq!(SignedDuration::from_secs(90).to_string_with_options(&options), "PT120S");
+    }
+
+    #[test]
+    fn zoned_leap_second() {
+        let result: ZonedDateTime = "2016-12-31T23:59:60Z[Asia/Tehran]".parse().unwrap();
+        assert_eq!(result.to_string(), "2017-01-01T03:29:59+03:30[Asia/Tehran]");
+        let result = ZonedDateTime::from_str("2016-12-31T23:59:59+03:61[Asia/Tehran]");
+        assert!(matches!(result, Err(e) if e.kind() == &ParseErrorKind::InvalidTime));
+        let result = "+24:00".parse::<TimeZone>();
+        assert_eq!(result.unwrap_err().offset(), 1);
+    }
+
+    #[test]
+    fn zoned_annotations() {
+        let s = "2022-09-01T04:30:00+04:30[Asia/Tehran][u-ca=chinese][foo=bar][_x=1]";
+        let result: ZonedDateTime = s.parse().unwrap();
+        assert_eq!(result.annotations().len(), 2);
+        assert_eq!(result.to_string(), s);
+        let result: ZonedDateTime = "2022-09-01T00:00Z[!Asia/Tehran][u-ca=iso8601]".parse().unwrap();
+        assert_eq!(result.to_string(), "2022-09-01T04:30:00+04:30[Asia/Tehran]");
+        assert!(matches!(
+            ZonedDateTime::from_str("2022-09-01T00:00Z[Asia/Tehran][!foo=bar]"),
+            Err(e) if e.kind() == &ParseErrorKind::UnknownCriticalAnnotation("foo".into()),
+        ));
+    }
+
+    #[test]
+    fn zoned_sub_second_offset() {
+        let s = "1900-01-01T00:00:00+02:35:53.1423[+02:35:53.1423]";
+        let resolve = |id: &str| TimeZone::parse_allowing_sub_second(id).ok();
+        let result = ZonedDateTime::parse_with(s, resolve).unwrap();
+        assert_eq!((result.day(), result.hour(), result.minute()), (1, 0, 0));
+        assert_eq!(result.to_string(), s);
+        let result = ZonedDateTime::parse_with("1900-01-01T00:00:00.9[+00:00:00.2]", resolve);
+        assert_eq!(result.unwrap().to_string(), "1900-01-01T00:00:00.9+00:00:00.2[+00:00:00.2]");
+        let result = ZonedDateTime::new(
+            SignedDuration::from_secs(0),
+            TimeZone::from_offset_nanoseconds(-1).unwrap(),
+            Calendar::Iso8601,
+        );
+        assert_eq!(
+            result.to_string(),
+            "1969-12-31T23:59:59.999999999-00:00:00.000000001[-00:00:00.000000001]"
+        );
+        assert!(matches!(
+            ZonedDateTime::from_str(s),
+            Err(e) if matches!(e.kind(), ParseErrorKind::UnknownTimeZone(_))
+        ));
+    }
+
+    #[test]
+    fn plain_date_fields() {
+        let date: PlainDate = "2023-03-22".parse().unwrap();
+        assert_eq!(date.month_code(), "M03");
+        assert_eq!(date.day(), 22);
+        assert_eq!(date.day_of_week(), 3);
+        assert_eq!(date.day_of_year(), 81);
+        assert_eq!(date.era(), None);
+        let chinese = date.with_calendar(Calendar::Chinese);
+        assert_eq!(chinese.month_code(), "M02L");
+        assert_eq!(chinese.day(), 1);
+        assert_eq!(chinese.era(), Some("gui-mao"));
+        assert_eq!(chinese.era_year(), Some(40));
+    }
+
+    #[test]
+    fn plain_date_compare() {
+        let a: PlainDate = "2023-03-22".parse().unwrap();
+        let b: PlainDate = "2023-03-23[u-ca=coptic]".parse().unwrap();
+        assert_eq!(a.compare(&b), Ordering::Less);
+        assert_eq!(b.compare(&a), Ordering::Greater);
+        let c = a.with_calendar(Calendar::Chinese);
+        assert_eq!(a.compare(&c), Ordering::Equal);
+        assert!(!a.equals(&c));
+        assert!(a.equals(&c.with_calendar(Calendar::Iso8601)));
+    }
+
+    #[test]
+    fn plain_date_with() {
+        let date: PlainDate = "2023-01-31".parse().unwrap();
+        let fields = DateFields {
+            month: Some(2),
+            ..Default::default()
+        };
+        let result = date.with(&fields).unwrap().constrain();
+        assert_eq!((result.month(), result.day()), (2, 28));
+        let date = date.with_calendar(Calendar::Ethiopic);
+        let fields = DateFields {
+            era: Some("aa".to_string()),
+            era_year: Some(5493),
+            ..Default::default()
+        };
+        let result = date.with(&fields).unwrap().constrain();
+        assert_eq!(result.year(), -7);
+        assert_eq!(result.month(), date.month());
+        let fields = DateFields {
+            month: Some(2),
+            month_code: Some("M03".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            date.with(&fields),
+            Err(DateFieldsError::InconsistentMonth)
         ));
     }
 
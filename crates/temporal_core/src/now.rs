@@ -0,0 +1,213 @@
+//! Reading the current time, through a clock which can be replaced in tests
+
+use std::{
+    cell::RefCell,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use crate::{
+    CalendarProtocol, IsoTime, PlainDate, SignedDuration, TimeZone, TimeZoneProtocol, ZonedDateTime,
+};
+
+/// Source of the current instant and time zone
+pub trait Clock {
+    /// Time since unix epoch
+    fn instant(&self) -> SignedDuration;
+
+    /// Time zone of the clock, which is the time zone of the system by default
+    fn time_zone(&self) -> TimeZone {
+        TimeZone::system()
+    }
+}
+
+impl<T: Clock + ?Sized> Clock for &T {
+    fn instant(&self) -> SignedDuration {
+        (**self).instant()
+    }
+
+    fn time_zone(&self) -> TimeZone {
+        (**self).time_zone()
+    }
+}
+
+impl<T: Clock + ?Sized> Clock for Arc<T> {
+    fn instant(&self) -> SignedDuration {
+        (**self).instant()
+    }
+
+    fn time_zone(&self) -> TimeZone {
+        (**self).time_zone()
+    }
+}
+
+/// The clock of the operating system
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn instant(&self) -> SignedDuration {
+        SignedDuration::from_system_time_since_unix(SystemTime::now())
+    }
+}
+
+/// A clock which stays at the same instant, unless it is set or advanced by hand
+#[derive(Debug)]
+pub struct ManualClock {
+    instant: Mutex<SignedDuration>,
+    time_zone: TimeZone,
+}
+
+impl ManualClock {
+    pub fn new(instant: SignedDuration, time_zone: TimeZone) -> Self {
+        Self {
+            instant: Mutex::new(instant),
+            time_zone,
+        }
+    }
+
+    pub fn set(&self, instant: SignedDuration) {
+        *self.instant.lock().unwrap() = instant;
+    }
+
+    pub fn advance(&self, by: SignedDuration) {
+        let mut instant = self.instant.lock().unwrap();
+        *instant = *instant + by;
+    }
+}
+
+impl Clock for ManualClock {
+    fn instant(&self) -> SignedDuration {
+        *self.instant.lock().unwrap()
+    }
+
+    fn time_zone(&self) -> TimeZone {
+        self.time_zone.clone()
+    }
+}
+
+thread_local! {
+    static THREAD_CLOCK: RefCell<Option<Arc<dyn Clock + Send + Sync>>> = RefCell::new(None);
+}
+
+/// The clock of the current thread, which is the [`SystemClock`] unless replaced by
+/// [`Now::with_thread_clock`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreadClock;
+
+impl Clock for ThreadClock {
+    fn instant(&self) -> SignedDuration {
+        THREAD_CLOCK.with(|c| match &*c.borrow() {
+            Some(c) => c.instant(),
+            None => SystemClock.instant(),
+        })
+    }
+
+    fn time_zone(&self) -> TimeZone {
+        THREAD_CLOCK.with(|c| match &*c.borrow() {
+            Some(c) => c.time_zone(),
+            None => SystemClock.time_zone(),
+        })
+    }
+}
+
+/// Current date and time, equivalent of `Temporal.Now`
+///
+/// `Now::new()` reads the clock of the current thread. Code that should be testable
+/// without thread locals can take a clock explicitly with [`Now::with_clock`].
+#[derive(Debug, Clone, Default)]
+pub struct Now<C: Clock = ThreadClock> {
+    clock: C,
+}
+
+impl Now {
+    pub fn new() -> Self {
+        Self { clock: ThreadClock }
+    }
+
+    /// Runs `f` with `clock` as the clock of the current thread. The previous clock is
+    /// restored afterwards, even if `f` panics.
+    pub fn with_thread_clock<R>(clock: Arc<dyn Clock + Send + Sync>, f: impl FnOnce() -> R) -> R {
+        struct Restore(Option<Arc<dyn Clock + Send + Sync>>);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                THREAD_CLOCK.with(|c| *c.borrow_mut() = self.0.take());
+            }
+        }
+
+        let _restore = Restore(THREAD_CLOCK.with(|c| c.borrow_mut().replace(clock)));
+        f()
+    }
+}
+
+impl<C: Clock> Now<C> {
+    pub fn with_clock(clock: C) -> Self {
+        Self { clock }
+    }
+
+    /// Time since unix epoch
+    pub fn instant(&self) -> SignedDuration {
+        self.clock.instant()
+    }
+
+    pub fn time_zone_id(&self) -> String {
+        self.clock.time_zone().id()
+    }
+
+    pub fn zoned_date_time<Cal: CalendarProtocol>(
+        &self,
+        calendar: Cal,
+    ) -> ZonedDateTime<TimeZone, Cal> {
+        ZonedDateTime::new(self.instant(), self.clock.time_zone(), calendar)
+    }
+
+    pub fn plain_date<Cal: CalendarProtocol>(&self, calendar: Cal) -> PlainDate<Cal> {
+        self.zoned_date_time(calendar).into()
+    }
+
+    /// Current wall clock time in the time zone of the clock
+    pub fn plain_time(&self) -> IsoTime {
+        self.zoned_date_time(crate::Calendar::Iso8601).iso_time()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{ManualClock, Now};
+    use crate::{Calendar, SignedDuration, TimeZone};
+
+    fn clock() -> ManualClock {
+        // 2022-02-02T23:30:00Z
+        let instant = SignedDuration::from_secs(1643844600);
+        ManualClock::new(instant, "+01:00".parse::<TimeZone>().unwrap())
+    }
+
+    #[test]
+    fn explicit_clock() {
+        let clock = clock();
+        let now = Now::with_clock(&clock);
+        assert_eq!(now.time_zone_id(), "+01:00");
+        let date = now.plain_date(Calendar::Iso8601);
+        assert_eq!((date.year(), date.month(), date.day()), (2022, 2, 3));
+        assert_eq!(now.plain_time().hour, 0);
+        clock.advance(SignedDuration::new(90, 500));
+        let time = now.plain_time();
+        assert_eq!((time.minute, time.second, time.nanosecond), (31, 30, 500));
+        clock.set(SignedDuration::from_secs(0));
+        assert_eq!(now.instant(), SignedDuration::from_secs(0));
+    }
+
+    #[test]
+    fn thread_clock() {
+        let clock = Arc::new(clock());
+        let instant = Now::with_thread_clock(clock.clone(), || {
+            clock.advance(SignedDuration::from_secs(10));
+            SignedDuration::now()
+        });
+        assert_eq!(instant, SignedDuration::from_secs(1643844610));
+        assert!(SignedDuration::now() > instant);
+    }
+}
@@ -1,6 +1,11 @@
-use std::str::FromStr;
+use alloc::string::{String, ToString};
+use core::{cmp::Ordering, fmt::Display, str::FromStr};
 
-use crate::iso::{self, IsoDate};
+use crate::{
+    error::ParseError,
+    format::{write_calendar, DisplayCalendar, ToStringOptions},
+    iso::{self, IsoDate},
+};
 
 use super::calendar::*;
 
@@ -9,7 +14,7 @@ pub struct PlainDate<C: CalendarProtocol = Calendar> {
     pub(crate) calendar: C,
     pub(crate) iso_year: i32,
     pub(crate) iso_month: u8,
-    pub(crate) iso_day: u16,
+    pub(crate) iso_day: u8,
 }
 
 const _: () = assert!(core::mem::size_of::<PlainDate>() == 8);
@@ -18,11 +23,7 @@ pub struct MaybeOutOfRangePlainDate<C: CalendarProtocol>(FromYMDResult, C);
 
 impl<C: CalendarProtocol> MaybeOutOfRangePlainDate<C> {
     pub fn constrain(self) -> PlainDate<C> {
-        match self.0 {
-            FromYMDResult::Normal(x) | FromYMDResult::OverflowConstrained(x) => {
-                PlainDate::from_iso_date(x, self.1)
-            }
-        }
+        PlainDate::from_iso_date(self.0.constrain(), self.1)
     }
 }
 
@@ -30,6 +31,40 @@ impl<C: CalendarProtocol + Copy> PlainDate<C> {
     pub fn calendar(self) -> C {
         self.calendar
     }
+
+    /// Returns the fields of this date, as used by [`PlainDate::with`]
+    pub fn fields(&self) -> DateFields {
+        let iso_date = self.iso_date();
+        let year = self.calendar.year(iso_date);
+        // Only include the era if it can be resolved back, which isn't the case for example
+        // for the cyclic years of the chinese calendar.
+        let era = self
+            .calendar
+            .era(iso_date)
+            .filter(|e| self.calendar.year_from_era(e.name, e.year) == Some(year));
+        DateFields {
+            year: Some(year),
+            month: Some(self.calendar.month(iso_date)),
+            month_code: Some(self.calendar.month_code(iso_date).to_string()),
+            day: Some(self.calendar.day(iso_date)),
+            era_year: era.as_ref().map(|e| e.year),
+            era: era.map(|e| e.name.to_string()),
+        }
+    }
+
+    /// Returns a new date with some of the fields replaced, for example
+    /// `date.with(&DateFields { day: Some(1), ..Default::default() })` for the first day
+    /// of the month.
+    pub fn with(
+        &self,
+        fields: &DateFields,
+    ) -> Result<MaybeOutOfRangePlainDate<C>, DateFieldsError> {
+        let merged = self.calendar.merge_fields(&self.fields(), fields);
+        Ok(MaybeOutOfRangePlainDate(
+            self.calendar.date_from_fields(&merged)?,
+            self.calendar,
+        ))
+    }
 }
 
 impl<C: CalendarProtocol> PlainDate<C> {
@@ -37,17 +72,27 @@ impl<C: CalendarProtocol> PlainDate<C> {
         MaybeOutOfRangePlainDate(calendar.from_ymd(year, month, day), calendar)
     }
 
+    pub fn from_fields(
+        fields: &DateFields,
+        calendar: C,
+    ) -> Result<MaybeOutOfRangePlainDate<C>, DateFieldsError> {
+        Ok(MaybeOutOfRangePlainDate(
+            calendar.date_from_fields(fields)?,
+            calendar,
+        ))
+    }
+
     pub fn from_iso_date(iso_date: IsoDate, calendar: C) -> Self {
         Self {
             calendar,
-            iso_day: iso_date.day(),
+            iso_day: iso_date.day() as u8,
             iso_month: iso_date.month(),
             iso_year: iso_date.year(),
         }
     }
 
     pub fn iso_date(&self) -> IsoDate {
-        IsoDate::new_unchecked(self.iso_year, self.iso_month, self.iso_day)
+        IsoDate::new_unchecked(self.iso_year, self.iso_month, self.iso_day.into())
     }
 
     pub fn year(&self) -> i32 {
@@ -57,12 +102,100 @@ impl<C: CalendarProtocol> PlainDate<C> {
     pub fn month(&self) -> u32 {
         self.calendar.month(self.iso_date())
     }
+
+    pub fn month_code(&self) -> MonthCode {
+        self.calendar.month_code(self.iso_date())
+    }
+
+    pub fn day(&self) -> u32 {
+        self.calendar.day(self.iso_date())
+    }
+
+    pub fn era(&self) -> Option<&'static str> {
+        self.calendar.era(self.iso_date()).map(|e| e.name)
+    }
+
+    pub fn era_year(&self) -> Option<u32> {
+        self.calendar.era(self.iso_date()).map(|e| e.year)
+    }
+
+    pub fn day_of_week(&self) -> u32 {
+        self.calendar.day_of_week(self.iso_date())
+    }
+
+    pub fn day_of_year(&self) -> u32 {
+        self.calendar.day_of_year(self.iso_date())
+    }
+
+    pub fn days_in_week(&self) -> u32 {
+        self.calendar.days_in_week(self.iso_date())
+    }
+
+    pub fn days_in_month(&self) -> u32 {
+        self.calendar.days_in_month(self.iso_date())
+    }
+
+    pub fn days_in_year(&self) -> u32 {
+        self.calendar.days_in_year(self.iso_date())
+    }
+
+    pub fn months_in_year(&self) -> u32 {
+        self.calendar.months_in_year(self.iso_date())
+    }
+
+    pub fn in_leap_year(&self) -> bool {
+        self.calendar.in_leap_year(self.iso_date())
+    }
+
+    /// Returns the same date in another calendar
+    pub fn with_calendar<D: CalendarProtocol>(&self, calendar: D) -> PlainDate<D> {
+        PlainDate::from_iso_date(self.iso_date(), calendar)
+    }
+
+    /// Compares the dates by their position in time, ignoring the calendars
+    pub fn compare<D: CalendarProtocol>(&self, other: &PlainDate<D>) -> Ordering {
+        (self.iso_year, self.iso_month, self.iso_day).cmp(&(
+            other.iso_year,
+            other.iso_month,
+            other.iso_day,
+        ))
+    }
+
+    /// Returns true if both the dates and the calendars are the same
+    pub fn equals<D: CalendarProtocol>(&self, other: &PlainDate<D>) -> bool {
+        self.compare(other) == Ordering::Equal && self.calendar.id() == other.calendar.id()
+    }
+}
+
+impl<C: CalendarProtocol> PlainDate<C> {
+    /// Writes the date like `Display`, with the calendar annotation shown according to
+    /// `options.calendar_name`
+    pub fn to_string_with_options(&self, options: &ToStringOptions) -> String {
+        let mut s = self.iso_date().to_string();
+        write_calendar(&mut s, self.calendar.id(), options.calendar_name).unwrap();
+        s
+    }
+}
+
+/// Writes the ISO date like `2022-02-02`, followed by `[u-ca=coptic]` if the calendar is
+/// not ISO 8601
+impl<C: CalendarProtocol> Display for PlainDate<C> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.iso_date())?;
+        write_calendar(f, self.calendar.id(), DisplayCalendar::Auto)
+    }
 }
 
 impl FromStr for PlainDate {
-    type Err = ();
-    fn from_str(x: &str) -> Result<Self, ()> {
-        let i = iso::parse(x).ok_or(())?.date;
-        Ok(Self::from_iso_date(i, Calendar::Iso8601))
+    type Err = ParseError;
+    fn from_str(x: &str) -> Result<Self, ParseError> {
+        let i = iso::parse(x)?;
+        let calendar = match i.calendar {
+            Some(c) => c
+                .parse::<Calendar>()
+                .map_err(|e| e.shifted(i.calendar_at))?,
+            None => Calendar::Iso8601,
+        };
+        Ok(Self::from_iso_date(i.date, calendar))
     }
 }
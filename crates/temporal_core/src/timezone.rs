@@ -1,96 +1,332 @@
-use std::{str::FromStr, fmt::Debug};
+use alloc::{format, string::{String, ToString}, sync::Arc, vec, vec::Vec};
+use core::{str::FromStr, fmt::{Debug, Display}};
 
-use crate::iso::{IsoDate, IsoTime, self, parse_time, parse_sign};
+use crate::{
+    error::{ParseError, ParseErrorKind},
+    iso::{IsoDate, IsoTime, self, parse_numeric_timezone, parse_sign, Cursor},
+    SignedDuration,
+};
 
+mod custom;
 mod iana_generated;
+mod location;
+mod posix;
+#[cfg(feature = "std")]
+mod system;
 mod timezone_impl;
+mod transition;
+mod tzif;
 
+pub use location::ZoneLocation;
+pub use transition::TimeZoneTransition;
+pub use tzif::TzifError;
+
+/// Rules of a time zone
+///
+/// Besides the builtin [`TimeZone`], this can be implemented for zones with other rules,
+/// like a company defined offset schedule. Such a zone needs an id, which is what its
+/// `ZonedDateTime`s show in brackets, and its offset at every instant. Zones whose offset
+/// changes should also implement the transition queries, which default to none.
 pub trait TimeZoneProtocol {
     fn id(&self) -> String;
+    /// Offset from UTC in seconds at an instant
     fn get_second_offset(&self, seconds_since_epoch: i64) -> i64;
+    /// Instants with the given local date and time, sorted. There are two in an overlap,
+    /// and none in a gap.
     fn get_possible_seconds(&self, date: IsoDate, time: IsoTime) -> Vec<i64>;
+
+    /// Offset from UTC in nanoseconds at an instant. Zones with sub-second offsets should
+    /// override this, and round down in `get_second_offset`.
+    fn get_nanosecond_offset(&self, epoch: SignedDuration) -> i64 {
+        self.get_second_offset(epoch.as_secs()) * 1_000_000_000
+    }
+
+    /// Instants with the given local date and time, like `get_possible_seconds` but keeping
+    /// the fraction of a second. Zones with sub-second offsets should override this.
+    fn get_possible_epochs(&self, date: IsoDate, time: IsoTime) -> Vec<SignedDuration> {
+        let nanos = (time.to_nanosecond() % 1_000_000_000) as i32;
+        self.get_possible_seconds(date, time)
+            .into_iter()
+            .map(|secs| SignedDuration::new(secs, nanos))
+            .collect()
+    }
+
+    /// Abbreviation and daylight saving status at an instant. By default, the offset
+    /// is considered standard time, and abbreviated to its numeric form like `+05:30`.
+    fn get_offset_info(&self, seconds_since_epoch: i64) -> OffsetInfo {
+        let offset = self.get_second_offset(seconds_since_epoch);
+        OffsetInfo {
+            abbreviation: format_offset(offset),
+            standard_offset: offset,
+            dst_offset: 0,
+            is_dst: false,
+        }
+    }
+
+    /// Returns the first instant strictly after `after` where the offset changes, or `None`
+    /// if it never changes again
+    fn next_transition(&self, after: SignedDuration) -> Option<TimeZoneTransition> {
+        let _ = after;
+        None
+    }
+
+    /// Returns the last instant strictly before `before` where the offset changed, or `None`
+    /// if it never changed before
+    fn previous_transition(&self, before: SignedDuration) -> Option<TimeZoneTransition> {
+        let _ = before;
+        None
+    }
+}
+
+/// Offset in effect at an instant, split into its standard and daylight saving parts
+///
+/// `dst_offset` is negative in zones whose daylight saving time is in winter, like
+/// `Europe/Dublin`, and TZif files may flag daylight saving time without giving its
+/// standard offset, so `is_dst` should be preferred to comparing `dst_offset` with zero.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OffsetInfo {
+    /// Abbreviation of the local time, like `EDT`, or a numeric offset when the zone
+    /// doesn't have one, which is `+0330` in the tz database and `+03:30` for offset
+    /// time zones
+    pub abbreviation: String,
+    /// Offset from UTC in seconds, without daylight saving time
+    pub standard_offset: i64,
+    /// Additional offset in seconds of daylight saving time
+    pub dst_offset: i64,
+    /// Whether the rules of the zone add a saving, even a negative one, as `zic` flags
+    /// daylight saving time
+    pub is_dst: bool,
+}
+
+impl OffsetInfo {
+    /// Total offset from UTC in seconds
+    pub fn offset(&self) -> i64 {
+        self.standard_offset + self.dst_offset
+    }
 }
 
 use iana_generated::Tz;
 
-use self::timezone_impl::TimeSpans;
+use self::{custom::CustomTimeZone, timezone_impl::TimeSpans, tzif::Tzif};
 
+/// Offset and abbreviation in effect at some instant
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LocalTimeType<'a> {
+    pub(crate) offset: i32,
+    pub(crate) std_offset: i32,
+    pub(crate) is_dst: bool,
+    pub(crate) name: &'a str,
+}
+
+impl From<LocalTimeType<'_>> for OffsetInfo {
+    fn from(t: LocalTimeType<'_>) -> Self {
+        OffsetInfo {
+            abbreviation: t.name.to_string(),
+            standard_offset: t.std_offset.into(),
+            dst_offset: (t.offset - t.std_offset).into(),
+            is_dst: t.is_dst,
+        }
+    }
+}
+
+/// Formats an offset in seconds like `+05:30`, or `+05:30:15` if it has seconds
+pub(crate) fn format_offset(offset: i64) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let x = offset.abs();
+    let secs = x % 60;
+    let x = x / 60;
+    let mins = x % 60;
+    let hours = x / 60;
+    if secs == 0 {
+        format!("{}{:02}:{:02}", sign, hours, mins)
+    } else {
+        format!("{}{:02}:{:02}:{:02}", sign, hours, mins, secs)
+    }
+}
+
+/// Formats an offset in nanoseconds like `format_offset`, with the fraction of a second if
+/// it has one, like `+02:35:53.1423`
+pub(crate) fn format_offset_nanoseconds(offset: i64) -> String {
+    let fraction = offset.unsigned_abs() % 1_000_000_000;
+    if fraction == 0 {
+        return format_offset(offset / 1_000_000_000);
+    }
+    let sign = if offset < 0 { '-' } else { '+' };
+    let x = offset.unsigned_abs() / 1_000_000_000;
+    let fraction = format!("{:09}", fraction);
+    format!(
+        "{}{:02}:{:02}:{:02}.{}",
+        sign,
+        x / 3600,
+        x / 60 % 60,
+        x % 60,
+        fraction.trim_end_matches('0')
+    )
+}
+
+/// Finds the instants with the given local date and time, by trying the offsets in effect
+/// a day before and after it. The result is sorted, and empty in a gap.
+pub(crate) fn possible_seconds<T: TimeZoneProtocol + ?Sized>(
+    tz: &T,
+    date: IsoDate,
+    time: IsoTime,
+) -> Vec<i64> {
+    let local = date.to_epoch_second() + i64::from(time.to_second());
+    let offsets = [
+        tz.get_second_offset(local - 86400),
+        tz.get_second_offset(local + 86400),
+    ];
+    let mut result: Vec<i64> = offsets
+        .iter()
+        .map(|offset| local - offset)
+        .filter(|&x| tz.get_second_offset(x) == local - x)
+        .collect();
+    result.sort_unstable();
+    result.dedup();
+    result
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 enum TimezoneInner {
     Tz(Tz),
-    Fixed(i32),
+    /// Offset in nanoseconds, which is whole seconds unless sub-second offsets were opted in
+    Fixed(i64),
+    Tzif(Arc<Tzif>),
+    Custom(CustomTimeZone),
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+/// A time zone: builtin, a fixed offset, loaded from a TZif file or user defined
+///
+/// Unlike `Calendar`, `TimeZone` is `Clone` but not `Copy`, as the rules of zones loaded at
+/// runtime are shared behind an `Arc`. Cloning builtin zones and offsets doesn't allocate.
+#[derive(Clone, PartialEq, Eq)]
 pub struct TimeZone(TimezoneInner);
 
 impl TimeZone {
     const UTC: Self = Self(TimezoneInner::Tz(Tz::UTC));
 
-    pub fn extract_from_iso_date(iso: &str) -> Result<Self, TimeZoneParseError> {
-        use TimeZoneParseError::*;
-        let i = iso::parse(iso).ok_or(InvalidIsoString)?;
+    pub fn extract_from_iso_date(iso: &str) -> Result<Self, ParseError> {
+        let i = iso::parse(iso)?;
         if let Some(x) = i.timezone_name {
-            return x.parse();
+            return x
+                .parse::<Self>()
+                .map_err(|e| e.shifted(i.timezone_name_at));
         }
-        Ok(match i.timezone_offset.ok_or(InvalidIsoString)? {
+        let offset = i
+            .timezone_offset
+            .ok_or(ParseError::new(ParseErrorKind::MissingOffset, iso.len()))?;
+        Ok(match offset {
             iso::IsoOffset::Z => Self::UTC,
-            iso::IsoOffset::Numeric(x) => Self(TimezoneInner::Fixed(x.to_seconds())),
+            iso::IsoOffset::Numeric(x) => {
+                let nanos = x.to_nanoseconds();
+                if nanos % 1_000_000_000 != 0 {
+                    let kind = ParseErrorKind::SubSecondOffset(nanos);
+                    return Err(ParseError::new(kind, i.timezone_offset_at));
+                }
+                Self(TimezoneInner::Fixed(nanos))
+            }
         })
     }
-}
 
-impl Debug for TimeZone {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_tuple("TimeZone").field(&self.id()).finish()
+    /// Creates a zone with a fixed offset in nanoseconds, which unlike ECMAScript is
+    /// rejected by parsing if it's not whole seconds. The offset should be less than a day.
+    pub fn from_offset_nanoseconds(offset: i64) -> Result<Self, ParseError> {
+        if offset.unsigned_abs() >= 86400 * 1_000_000_000 {
+            return Err(ParseError::new(ParseErrorKind::OutOfRange, 0));
+        }
+        Ok(Self(TimezoneInner::Fixed(offset)))
+    }
+
+    /// Parses a zone like `FromStr`, but accepting sub-second offsets like `+02:35:53.1423`
+    pub fn parse_allowing_sub_second(s: &str) -> Result<Self, ParseError> {
+        match s.parse::<Self>() {
+            Err(e) => match *e.kind() {
+                ParseErrorKind::SubSecondOffset(x) => Self::from_offset_nanoseconds(x),
+                _ => Err(e),
+            },
+            r => r,
+        }
+    }
+
+    /// Release of the tz database the builtin time zones were built from, like `2024a`,
+    /// if it was known at build time
+    pub fn database_version() -> Option<&'static str> {
+        iana_generated::DATABASE_VERSION
+    }
+
+    /// Ids of all the builtin time zones, including links like `US/Eastern`, sorted
+    pub fn available_ids() -> impl Iterator<Item = &'static str> {
+        iana_generated::TZ_VARIANTS.iter().map(|tz| tz.name())
+    }
+
+    /// The builtin zone with the same id, which TZif zones loaded by name usually have
+    fn as_builtin(&self) -> Option<Tz> {
+        match &self.0 {
+            TimezoneInner::Tz(x) => Some(*x),
+            TimezoneInner::Tzif(x) => x.id.parse().ok(),
+            TimezoneInner::Fixed(_) | TimezoneInner::Custom(_) => None,
+        }
+    }
+
+    /// Whether the id of this zone is a link to another zone, like `US/Eastern` which
+    /// is a link to `America/New_York`
+    pub fn is_link(&self) -> bool {
+        self.as_builtin().is_some_and(|tz| tz.canonical() != tz)
+    }
+
+    /// The id of the zone, with links resolved to the zone they refer to. As in
+    /// ECMAScript, the canonical id of `Etc/UTC` and `Etc/GMT` is `UTC`.
+    pub fn canonical_id(&self) -> String {
+        match self.as_builtin().map(Tz::canonical) {
+            Some(Tz::Etc__UTC | Tz::Etc__GMT) => "UTC".to_string(),
+            Some(tz) => tz.name().to_string(),
+            None => self.id(),
+        }
+    }
+
+    /// Whether both zones have the same canonical id, so `US/Eastern` equals
+    /// `America/New_York`, unlike with `==` which compares ids and data exactly
+    pub fn equals(&self, other: &TimeZone) -> bool {
+        self.canonical_id() == other.canonical_id()
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub enum TimeZoneParseError {
-    InvalidTimeZone,
-    InvalidIsoString,
-    OutOfRange,
-    SubSecondOffset(i64),
+/// Writes the id of the zone, which parses back to the same zone unless it's custom
+impl Display for TimeZone {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(&self.id())
+    }
 }
 
-impl TimeZoneParseError {
-    pub fn is_sub_second(&self) -> Option<i64> {
-        match self {
-            Self::SubSecondOffset(x) => Some(*x),
-            _ => None
+impl Debug for TimeZone {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut f = f.debug_tuple("TimeZone");
+        f.field(&self.id());
+        // Builtin zones show the release of their data, to tell which rules a binary has
+        if let (TimezoneInner::Tz(_), Some(version)) = (&self.0, Self::database_version()) {
+            f.field(&format_args!("tzdb {}", version));
         }
+        f.finish()
     }
 }
 
 impl FromStr for TimeZone {
-    type Err = TimeZoneParseError;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        use TimeZoneParseError::*;
-        let mut it = s.chars().peekable();
         if let Ok(t) = Tz::from_str_insensitive(s) {
             return Ok(Self(TimezoneInner::Tz(t)));
         }
-        if let Some(c) = it.next() {
-            if let Some(is_neg) = parse_sign(c) {
-                if let Some(t) = parse_time(&mut it, true) {
-                    if it.next().is_some() {
-                        return Err(InvalidIsoString);
-                    }
-                    if t.has_nanosecond() {
-                        let t = t.to_nanosecond();
-                        let r = if is_neg { -t } else { t };
-                        return Err(SubSecondOffset(r));
-                    }
-                    let t = t.to_second();
-                    let r = if is_neg { -t } else { t };
-                    return Ok(Self(TimezoneInner::Fixed(r)));
-                }
-                return Err(InvalidIsoString);
-            }
+        if !s.starts_with(|c| parse_sign(c).is_some()) {
+            return Err(ParseError::new(ParseErrorKind::UnknownTimeZone(s.to_string()), 0));
+        }
+        let mut cur = Cursor::new(s);
+        let offset = parse_numeric_timezone(&mut cur)?.to_nanoseconds();
+        cur.finish()?;
+        if offset % 1_000_000_000 != 0 {
+            return Err(ParseError::new(ParseErrorKind::SubSecondOffset(offset), 0));
         }
-        Err(InvalidTimeZone)
+        Ok(Self(TimezoneInner::Fixed(offset)))
     }
 }
 
@@ -98,19 +334,9 @@ impl TimeZoneProtocol for TimeZone {
     fn id(&self) -> String {
         match &self.0 {
             TimezoneInner::Tz(x) => x.name().to_string(),
-            TimezoneInner::Fixed(x) => {
-                let sign = if *x < 0 { '-' } else { '+' };
-                let x = x.abs();
-                let secs = x % 60;
-                let x = x / 60;
-                let mins = x % 60;
-                let hours = x / 60;
-                if secs == 0 {
-                    format!("{}{:02}:{:02}", sign, hours, mins)
-                } else {
-                    format!("{}{:02}:{:02}:{:02}", sign, hours, mins, secs)    
-                }
-            },
+            TimezoneInner::Tzif(x) => x.id.clone(),
+            TimezoneInner::Fixed(x) => format_offset_nanoseconds(*x),
+            TimezoneInner::Custom(x) => x.0.id(),
         }
     }
 
@@ -120,18 +346,80 @@ impl TimeZoneProtocol for TimeZone {
                 .timespans()
                 .select_with_sec(seconds_since_epoch)
                 .second_offset(),
-            TimezoneInner::Fixed(x) => (*x).into(),
+            TimezoneInner::Fixed(x) => x.div_euclid(1_000_000_000),
+            TimezoneInner::Tzif(x) => x.local_time_type(seconds_since_epoch).offset.into(),
+            TimezoneInner::Custom(x) => x.0.get_second_offset(seconds_since_epoch),
         }
     }
 
     fn get_possible_seconds(&self, date: IsoDate, time: IsoTime) -> Vec<i64> {
-        todo!()
+        match &self.0 {
+            TimezoneInner::Custom(x) => x.0.get_possible_seconds(date, time),
+            _ => possible_seconds(self, date, time),
+        }
+    }
+
+    fn get_nanosecond_offset(&self, epoch: SignedDuration) -> i64 {
+        match &self.0 {
+            TimezoneInner::Fixed(x) => *x,
+            TimezoneInner::Custom(x) => x.0.get_nanosecond_offset(epoch),
+            _ => self.get_second_offset(epoch.as_secs()) * 1_000_000_000,
+        }
+    }
+
+    fn get_possible_epochs(&self, date: IsoDate, time: IsoTime) -> Vec<SignedDuration> {
+        match &self.0 {
+            TimezoneInner::Fixed(x) => {
+                let local = date.to_epoch_second() + i64::from(time.to_second());
+                let nanos = (time.to_nanosecond() % 1_000_000_000) as i32;
+                vec![SignedDuration::new(local, nanos).add_nanoseconds(-x)]
+            }
+            TimezoneInner::Custom(x) => x.0.get_possible_epochs(date, time),
+            _ => {
+                let nanos = (time.to_nanosecond() % 1_000_000_000) as i32;
+                possible_seconds(self, date, time)
+                    .into_iter()
+                    .map(|secs| SignedDuration::new(secs, nanos))
+                    .collect()
+            }
+        }
+    }
+
+    fn get_offset_info(&self, seconds_since_epoch: i64) -> OffsetInfo {
+        match &self.0 {
+            TimezoneInner::Tz(x) => {
+                let span = x.timespans().select_with_sec(seconds_since_epoch);
+                OffsetInfo {
+                    abbreviation: span.name.to_string(),
+                    standard_offset: span.utc_offset.into(),
+                    dst_offset: span.dst_offset.into(),
+                    // The saving of the rule, like `zic` even when it's negative
+                    is_dst: span.dst_offset != 0,
+                }
+            }
+            TimezoneInner::Fixed(x) => OffsetInfo {
+                abbreviation: format_offset_nanoseconds(*x),
+                standard_offset: x.div_euclid(1_000_000_000),
+                dst_offset: 0,
+                is_dst: false,
+            },
+            TimezoneInner::Tzif(x) => x.local_time_type(seconds_since_epoch).into(),
+            TimezoneInner::Custom(x) => x.0.get_offset_info(seconds_since_epoch),
+        }
+    }
+
+    fn next_transition(&self, after: SignedDuration) -> Option<TimeZoneTransition> {
+        TimeZone::next_transition(self, after)
+    }
+
+    fn previous_transition(&self, before: SignedDuration) -> Option<TimeZoneTransition> {
+        TimeZone::previous_transition(self, before)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{TimeZone, TimeZoneProtocol, timezone::TimeZoneParseError};
+    use crate::{IsoDate, ParseErrorKind, SignedDuration, TimeZone, TimeZoneProtocol};
 
     #[test]
     fn simple_parse() {
@@ -147,7 +435,148 @@ mod tests {
 
     #[test]
     fn parse_sub_second() {
-        let tz: Result<TimeZone, TimeZoneParseError> = "-03:30:00.000000001".parse();
-        assert_eq!(tz, Err(TimeZoneParseError::SubSecondOffset(-12600_000000001)));
+        let tz = "-03:30:00.000000001".parse::<TimeZone>().unwrap_err();
+        assert_eq!(tz.kind(), &ParseErrorKind::SubSecondOffset(-12600_000000001));
+    }
+
+    #[test]
+    fn sub_second_opt_in() {
+        let tz = TimeZone::parse_allowing_sub_second("+02:35:53.1423").unwrap();
+        assert_eq!(tz.id(), "+02:35:53.1423");
+        assert_eq!(tz.get_second_offset(0), 9353);
+        assert_eq!(tz.get_nanosecond_offset(SignedDuration::from_secs(0)), 9_353_142_300_000);
+        assert_eq!(tz, TimeZone::from_offset_nanoseconds(9_353_142_300_000).unwrap());
+        let tz = TimeZone::from_offset_nanoseconds(-500_000_000).unwrap();
+        assert_eq!(tz.to_string(), "-00:00:00.5");
+        assert_eq!(tz.get_second_offset(0), -1);
+        assert_eq!(
+            TimeZone::parse_allowing_sub_second("+01:00").unwrap(),
+            "+01:00".parse().unwrap()
+        );
+        assert_eq!(
+            TimeZone::from_offset_nanoseconds(86_400_000_000_000).unwrap_err().kind(),
+            &ParseErrorKind::OutOfRange
+        );
+        let e = TimeZone::extract_from_iso_date("2000-01-01T00:00+00:00:00.1").unwrap_err();
+        assert_eq!(e.kind(), &ParseErrorKind::SubSecondOffset(100_000_000));
+        assert_eq!(e.offset(), 16);
+    }
+
+    #[test]
+    fn far_future_dst() {
+        let tz: TimeZone = "Europe/Berlin".parse().unwrap();
+        let summer = IsoDate::new(2150, 7, 1).unwrap().to_epoch_second();
+        let winter = IsoDate::new(2150, 12, 1).unwrap().to_epoch_second();
+        assert_eq!(tz.get_second_offset(summer), 7200);
+        assert_eq!(tz.get_second_offset(winter), 3600);
+    }
+
+    #[test]
+    fn database_version() {
+        let version = TimeZone::database_version().unwrap();
+        assert!(version.starts_with(|c: char| c.is_ascii_digit()));
+        let tz: TimeZone = "Asia/Tehran".parse().unwrap();
+        assert_eq!(
+            format!("{:?}", tz),
+            format!("TimeZone(\"Asia/Tehran\", tzdb {})", version)
+        );
+        let tz: TimeZone = "+01:00".parse().unwrap();
+        assert_eq!(format!("{:?}", tz), "TimeZone(\"+01:00\")");
+    }
+
+    #[test]
+    #[cfg(not(feature = "drop-backward-links"))]
+    fn links() {
+        let link: TimeZone = "us/eastern".parse().unwrap();
+        assert_eq!(link.id(), "US/Eastern");
+        assert!(link.is_link());
+        assert_eq!(link.canonical_id(), "America/New_York");
+        let zone: TimeZone = "America/New_York".parse().unwrap();
+        assert!(!zone.is_link());
+        assert!(link.equals(&zone));
+        assert_ne!(link, zone);
+        assert!(!zone.equals(&"Europe/Berlin".parse().unwrap()));
+        let utc: TimeZone = "Etc/UTC".parse().unwrap();
+        assert_eq!(utc.canonical_id(), "UTC");
+        assert!(!utc.equals(&"+00:00".parse().unwrap()));
+        assert!(TimeZone::available_ids().any(|id| id == "US/Eastern"));
+        assert!(TimeZone::available_ids().all(|id| id.parse::<TimeZone>().is_ok()));
+    }
+
+    #[test]
+    #[cfg(feature = "drop-backward-links")]
+    fn without_backward_links() {
+        assert!("US/Eastern".parse::<TimeZone>().is_err());
+        assert!(!TimeZone::available_ids().any(|id| id == "US/Eastern"));
+        // Kept as the id of `TimeZone::UTC`
+        let utc: TimeZone = "UTC".parse().unwrap();
+        assert!(utc.is_link());
+        assert_eq!(utc.canonical_id(), "UTC");
+    }
+
+    #[test]
+    #[cfg(feature = "filter-by-regex")]
+    fn filtered_ids() {
+        // The zones the crate refers to by name are kept whatever the filter
+        for id in ["UTC", "Etc/UTC", "Etc/GMT"] {
+            assert!(id.parse::<TimeZone>().is_ok(), "{}", id);
+        }
+        for id in TimeZone::available_ids() {
+            let canonical = id.parse::<TimeZone>().unwrap().canonical_id();
+            assert!(TimeZone::available_ids().any(|id| id == canonical), "{}", id);
+        }
+        // A filter without regex syntax, like `Europe/`, keeps the ids containing it, and
+        // the ids linked to them
+        let filter = option_env!("CHRONO_TZ_TIMEZONE_FILTER").unwrap_or("").trim();
+        if filter.is_empty() || !filter.chars().all(|c| c.is_alphanumeric() || c == '/') {
+            return;
+        }
+        let kept = |id: &str| id.contains(filter) || id.starts_with("Etc/") || id == "UTC";
+        let canonical = |id: &str| id.parse::<TimeZone>().unwrap().canonical_id();
+        for id in TimeZone::available_ids() {
+            assert!(
+                TimeZone::available_ids().any(|x| kept(x) && canonical(x) == canonical(id)),
+                "{}",
+                id
+            );
+        }
+    }
+
+    #[test]
+    fn offset_info() {
+        let tz: TimeZone = "America/New_York".parse().unwrap();
+        let summer = IsoDate::new(2023, 7, 1).unwrap().to_epoch_second();
+        let info = tz.get_offset_info(summer);
+        assert_eq!(info.abbreviation, "EDT");
+        assert_eq!((info.standard_offset, info.dst_offset), (-5 * 3600, 3600));
+        assert!(info.is_dst);
+        let info = tz.get_offset_info(IsoDate::new(2150, 1, 1).unwrap().to_epoch_second());
+        assert_eq!(info.abbreviation, "EST");
+        assert!(!info.is_dst);
+        let tz: TimeZone = "Europe/Dublin".parse().unwrap();
+        let info = tz.get_offset_info(IsoDate::new(2023, 1, 1).unwrap().to_epoch_second());
+        assert_eq!(info.abbreviation, "GMT");
+        assert_eq!((info.standard_offset, info.dst_offset), (3600, -3600));
+        assert!(info.is_dst);
+        assert!(!tz.get_offset_info(summer).is_dst);
+        let tz: TimeZone = "Asia/Tehran".parse().unwrap();
+        assert_eq!(tz.get_offset_info(summer).abbreviation, "+0330");
+        let tz: TimeZone = "-03:30".parse().unwrap();
+        let info = tz.get_offset_info(summer);
+        assert_eq!(info.abbreviation, "-03:30");
+        assert_eq!(info.offset(), -12600);
+        assert!(!info.is_dst);
+    }
+
+    #[test]
+    fn posix_tz() {
+        let tz = TimeZone::from_posix_tz("EST5EDT,M3.2.0,M11.1.0").unwrap();
+        assert_eq!(tz.id(), "EST5EDT,M3.2.0,M11.1.0");
+        let summer = IsoDate::new(2023, 7, 1).unwrap().to_epoch_second();
+        assert_eq!(tz.get_second_offset(summer), -4 * 3600);
+        assert_eq!(
+            TimeZone::from_posix_tz("Europe/Berlin").unwrap_err().kind(),
+            &ParseErrorKind::UnknownTimeZone("Europe/Berlin".into())
+        );
     }
 }
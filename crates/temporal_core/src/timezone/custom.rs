@@ -0,0 +1,160 @@
+//! User defined time zones
+//!
+//! A type implementing [`TimeZoneProtocol`] can be used directly as the zone of a
+//! `ZonedDateTime<T>`, or wrapped with [`TimeZone::custom`] to be used where the builtin
+//! [`TimeZone`] is expected. Unlike custom calendars, `TimeZone` isn't `Copy`, so it holds
+//! the implementation itself instead of an index into a registry.
+
+use alloc::sync::Arc;
+use core::fmt::Debug;
+
+use super::{TimeZone, TimeZoneProtocol, TimezoneInner};
+
+#[derive(Clone)]
+pub(super) struct CustomTimeZone(pub(super) Arc<dyn TimeZoneProtocol + Send + Sync>);
+
+impl Debug for CustomTimeZone {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("CustomTimeZone").field(&self.0.id()).finish()
+    }
+}
+
+/// Custom zones are only equal to the clones of themselves, as their rules can't be
+/// compared
+impl PartialEq for CustomTimeZone {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for CustomTimeZone {}
+
+impl TimeZone {
+    /// Wraps a user defined zone, like one following a company defined offset schedule.
+    ///
+    /// The zone should implement the transition queries of [`TimeZoneProtocol`] if its
+    /// offset ever changes. To parse it back from strings like `2024-01-01T00:00+02:00[id]`,
+    /// resolve its id with [`ZonedDateTime::parse_with`](crate::ZonedDateTime::parse_with).
+    pub fn custom(zone: impl TimeZoneProtocol + Send + Sync + 'static) -> Self {
+        Self(TimezoneInner::Custom(CustomTimeZone(Arc::new(zone))))
+    }
+
+    /// Whether this zone was created with [`TimeZone::custom`]
+    pub fn is_custom(&self) -> bool {
+        matches!(self.0, TimezoneInner::Custom(_))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, vec::Vec};
+
+    use crate::{
+        timezone::possible_seconds, IsoDate, IsoTime, ParseErrorKind, SignedDuration, TimeZone,
+        TimeZoneProtocol, TimeZoneTransition, ZonedDateTime,
+    };
+
+    /// Follows `+01:00`, except for `+02:00` during the second half of 2024
+    struct Factory;
+
+    impl Factory {
+        fn schedule() -> [(i64, i64); 2] {
+            let epoch = |month| IsoDate::new(2024, month, 1).unwrap().to_epoch_second();
+            [(epoch(7), 7200), (epoch(12) + 31 * 86400, 3600)]
+        }
+
+        fn transition(i: usize) -> TimeZoneTransition {
+            let schedule = Self::schedule();
+            TimeZoneTransition {
+                epoch: SignedDuration::from_secs(schedule[i].0),
+                offset_before: if i == 0 { 3600 } else { schedule[i - 1].1 },
+                offset_after: schedule[i].1,
+            }
+        }
+    }
+
+    impl TimeZoneProtocol for Factory {
+        fn id(&self) -> String {
+            "Acme/Factory".into()
+        }
+
+        fn get_second_offset(&self, seconds_since_epoch: i64) -> i64 {
+            Self::schedule()
+                .iter()
+                .rev()
+                .find(|(start, _)| *start <= seconds_since_epoch)
+                .map_or(3600, |(_, offset)| *offset)
+        }
+
+        fn get_possible_seconds(&self, date: IsoDate, time: IsoTime) -> Vec<i64> {
+            possible_seconds(self, date, time)
+        }
+
+        fn next_transition(&self, after: SignedDuration) -> Option<TimeZoneTransition> {
+            let i = Self::schedule()
+                .iter()
+                .position(|(start, _)| *start > after.as_secs())?;
+            Some(Self::transition(i))
+        }
+
+        fn previous_transition(&self, before: SignedDuration) -> Option<TimeZoneTransition> {
+            let i = Self::schedule()
+                .iter()
+                .rposition(|(start, _)| SignedDuration::from_secs(*start) < before)?;
+            Some(Self::transition(i))
+        }
+    }
+
+    fn resolve(id: &str) -> Option<TimeZone> {
+        match id {
+            "Acme/Factory" => Some(TimeZone::custom(Factory)),
+            _ => id.parse().ok(),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let s = "2024-07-15T12:00:00.25+02:00[Acme/Factory]";
+        let zdt =
+            ZonedDateTime::parse_with(s, |id| (id == "Acme/Factory").then_some(Factory)).unwrap();
+        assert_eq!((zdt.hour(), zdt.offset_info().offset()), (12, 7200));
+        assert_eq!(zdt.to_string(), s);
+        let zdt = ZonedDateTime::parse_with(s, resolve).unwrap();
+        assert_eq!(zdt.to_string(), s);
+        let zdt = ZonedDateTime::parse_with("2025-01-02T00:00[Acme/Factory]", resolve).unwrap();
+        assert_eq!(zdt.to_string(), "2025-01-02T00:00:00+01:00[Acme/Factory]");
+        let zdt = ZonedDateTime::parse_with("2024-01-01T00:00Z[Asia/Tehran]", resolve).unwrap();
+        assert_eq!(zdt.to_string(), "2024-01-01T03:30:00+03:30[Asia/Tehran]");
+        assert!(matches!(
+            ZonedDateTime::parse_with("2024-07-15T12:00Z[Acme/Office]", resolve),
+            Err(e) if e.kind() == &ParseErrorKind::UnknownTimeZone("Acme/Office".into())
+                && e.offset() == 18
+        ));
+    }
+
+    #[test]
+    fn wrapped() {
+        let tz = TimeZone::custom(Factory);
+        assert!(tz.is_custom());
+        assert_eq!(tz.to_string(), "Acme/Factory");
+        assert_eq!(tz.canonical_id(), "Acme/Factory");
+        assert_eq!(tz.location(), None);
+        assert_eq!(tz, tz.clone());
+        assert_ne!(tz, TimeZone::custom(Factory));
+        assert!(tz.equals(&TimeZone::custom(Factory)));
+        let start = SignedDuration::from_secs(0);
+        let t = tz.next_transition(start).unwrap();
+        assert_eq!((t.offset_before, t.offset_after), (3600, 7200));
+        let t = tz.next_transition(t.epoch).unwrap();
+        assert_eq!((t.offset_before, t.offset_after), (7200, 3600));
+        assert_eq!(tz.next_transition(t.epoch), None);
+        assert_eq!(
+            tz.previous_transition(t.epoch),
+            Some(Factory::transition(0))
+        );
+        assert_eq!(tz.previous_transition(start), None);
+        let info = tz.get_offset_info(t.epoch.as_secs() - 1);
+        assert_eq!((info.abbreviation.as_str(), info.is_dst), ("+02:00", false));
+        assert_eq!(format!("{:?}", tz), "TimeZone(\"Acme/Factory\")");
+    }
+}
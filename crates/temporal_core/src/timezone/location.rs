@@ -0,0 +1,91 @@
+//! Locations of time zones, and the countries using them, from `zone1970.tab` and
+//! `iso3166.tab`
+
+use super::{
+    iana_generated::{COUNTRY_NAMES, ZONE_LOCATIONS},
+    TimeZone, TimezoneInner,
+};
+
+/// Where a time zone is used, as described by the tz database
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZoneLocation {
+    /// ISO 3166 alpha-2 codes of the countries overlapping the zone. The country of the
+    /// principal location comes first, and the others are sorted.
+    pub country_codes: &'static [&'static str],
+    /// Latitude of the principal location, in degrees north
+    pub latitude: f64,
+    /// Longitude of the principal location, in degrees east
+    pub longitude: f64,
+    /// Distinguishes the zones of a country which has several, and is empty otherwise
+    pub comment: &'static str,
+}
+
+impl TimeZone {
+    /// Location of the zone. Links have the location of the zone they refer to, and
+    /// zones which aren't used in any country, like `UTC`, have none.
+    pub fn location(&self) -> Option<ZoneLocation> {
+        let tz = self.as_builtin()?.canonical();
+        ZONE_LOCATIONS
+            .iter()
+            .find(|(x, _)| *x == tz)
+            .map(|(_, location)| *location)
+    }
+
+    /// Zones used in the country with the given ISO 3166 alpha-2 code, like `IR`. Zones
+    /// come with the most populous first, where it makes geographical sense.
+    pub fn zones_for_country(code: &str) -> impl Iterator<Item = TimeZone> + '_ {
+        ZONE_LOCATIONS
+            .iter()
+            .filter(move |(_, location)| {
+                location
+                    .country_codes
+                    .iter()
+                    .any(|c| c.eq_ignore_ascii_case(code))
+            })
+            .map(|(tz, _)| TimeZone(TimezoneInner::Tz(*tz)))
+    }
+
+    /// English name of the country with the given ISO 3166 alpha-2 code
+    pub fn country_name(code: &str) -> Option<&'static str> {
+        COUNTRY_NAMES
+            .iter()
+            .find(|(c, _)| c.eq_ignore_ascii_case(code))
+            .map(|(_, name)| *name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{TimeZone, TimeZoneProtocol};
+
+    #[test]
+    fn locations() {
+        let tz: TimeZone = "America/New_York".parse().unwrap();
+        let location = tz.location().unwrap();
+        assert_eq!(location.country_codes, ["US"]);
+        assert_eq!(location.comment, "Eastern (most areas)");
+        assert!((location.latitude - 40.714).abs() < 0.001);
+        assert!((location.longitude + 74.006).abs() < 0.001);
+        assert_eq!(TimeZone::UTC.location(), None);
+    }
+
+    #[test]
+    #[cfg(not(feature = "drop-backward-links"))]
+    fn link_locations() {
+        let tz: TimeZone = "US/Eastern".parse().unwrap();
+        let zone: TimeZone = "America/New_York".parse().unwrap();
+        assert_eq!(tz.location(), zone.location());
+    }
+
+    #[test]
+    fn countries() {
+        let zones = TimeZone::zones_for_country("ir").collect::<Vec<_>>();
+        assert_eq!(zones.len(), 1);
+        assert_eq!(zones[0].id(), "Asia/Tehran");
+        // Denmark has followed the time of Germany since 1970
+        assert!(TimeZone::zones_for_country("DK").any(|tz| tz.id() == "Europe/Berlin"));
+        assert_eq!(TimeZone::zones_for_country("XX").count(), 0);
+        assert_eq!(TimeZone::country_name("IR"), Some("Iran"));
+        assert_eq!(TimeZone::country_name("XX"), None);
+    }
+}
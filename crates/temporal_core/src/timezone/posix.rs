@@ -0,0 +1,417 @@
+//! POSIX TZ strings, like `CET-1CEST,M3.5.0,M10.5.0/3`, which describe a standard offset
+//! and optionally a daylight saving rule recurring every year.
+//!
+//! We accept the extensions of RFC 8536, that is, transition times from -167 to 167 hours.
+
+use alloc::{
+    string::{String, ToString},
+    sync::Arc,
+};
+
+use super::{tzif::Tzif, LocalTimeType, TimeZone, TimezoneInner};
+use crate::{
+    error::{ParseError, ParseErrorKind},
+    iso::IsoDate,
+};
+
+/// Offsets in this module are in seconds east of UTC, unlike in the TZ string itself.
+///
+/// Names are borrowed from the parsed string, or owned by zones loaded at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PosixTimeZone<S = String> {
+    pub(crate) std_name: S,
+    pub(crate) std_offset: i32,
+    pub(crate) dst: Option<PosixDst<S>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PosixDst<S = String> {
+    pub(crate) name: S,
+    pub(crate) offset: i32,
+    /// Transition to daylight saving time, in local standard time
+    pub(crate) start: PosixTransition,
+    /// Transition back to standard time, in local daylight saving time
+    pub(crate) end: PosixTransition,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PosixTransition {
+    pub(crate) date: PosixDate,
+    /// Seconds since the start of the day
+    pub(crate) time: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PosixDate {
+    /// `Jn`: day of year from 1 to 365, where February 29th is never counted
+    JulianNoLeap(u16),
+    /// `n`: day of year from 0 to 365, counting February 29th
+    Julian(u16),
+    /// `Mm.w.d`: day `d` (0 is Sunday) of week `w` of month `m`, where week 5 is the last
+    MonthWeekDay { month: u8, week: u8, day: u8 },
+}
+
+fn is_leap(year: i32) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+impl PosixDate {
+    /// Epoch day of this date in the given year
+    fn epoch_day(self, year: i32) -> i64 {
+        let jan1 = IsoDate::new_unchecked(year, 1, 1).to_epoch_day();
+        match self {
+            PosixDate::JulianNoLeap(n) => {
+                let leap_day = (is_leap(year) && n >= 60) as i64;
+                jan1 + n as i64 - 1 + leap_day
+            }
+            PosixDate::Julian(n) => jan1 + n as i64,
+            PosixDate::MonthWeekDay { month, week, day } => {
+                let first = IsoDate::new_unchecked(year, month, 1).to_epoch_day();
+                let next = if month == 12 {
+                    IsoDate::new_unchecked(year + 1, 1, 1)
+                } else {
+                    IsoDate::new_unchecked(year, month + 1, 1)
+                }
+                .to_epoch_day();
+                // 1970-01-01 is a thursday
+                let first_weekday = (first + 4).rem_euclid(7);
+                let mut result =
+                    first + (day as i64 - first_weekday).rem_euclid(7) + 7 * (week as i64 - 1);
+                while result >= next {
+                    result -= 7;
+                }
+                result
+            }
+        }
+    }
+}
+
+impl PosixTransition {
+    /// Seconds since epoch of this transition in the given year, when the local time
+    /// has the given offset
+    fn epoch_second(self, year: i32, offset: i32) -> i64 {
+        self.date.epoch_day(year) * 86400 + self.time as i64 - offset as i64
+    }
+}
+
+impl<S> PosixDst<S> {
+    /// Returns the start and end of daylight saving time in the given year
+    pub(crate) fn transitions(&self, year: i32, std_offset: i32) -> (i64, i64) {
+        (
+            self.start.epoch_second(year, std_offset),
+            self.end.epoch_second(year, self.offset),
+        )
+    }
+}
+
+impl<S: AsRef<str>> PosixTimeZone<S> {
+    pub(crate) fn is_dst(&self, seconds_since_epoch: i64) -> bool {
+        let dst = match &self.dst {
+            Some(dst) => dst,
+            None => return false,
+        };
+        let year = IsoDate::from_epoch_second(seconds_since_epoch + self.std_offset as i64).year();
+        let (start, end) = dst.transitions(year, self.std_offset);
+        if start <= end {
+            start <= seconds_since_epoch && seconds_since_epoch < end
+        } else {
+            // Southern hemisphere, daylight saving time spans the new year
+            !(end <= seconds_since_epoch && seconds_since_epoch < start)
+        }
+    }
+
+    /// Instants in the years around `seconds_since_epoch` where daylight saving time
+    /// starts or ends, sorted, with the offsets before and after them. Transitions which
+    /// cancel out, like in a permanent daylight saving time, keep the same offset.
+    fn transitions_around(&self, seconds_since_epoch: i64) -> Option<[(i64, i64, i64); 6]> {
+        let dst = self.dst.as_ref()?;
+        let year = IsoDate::from_epoch_second(seconds_since_epoch + self.std_offset as i64).year();
+        let mut result = [(0, 0, 0); 6];
+        for (i, year) in (year - 1..=year + 1).enumerate() {
+            let (start, end) = dst.transitions(year, self.std_offset);
+            for (j, t) in [start, end].into_iter().enumerate() {
+                let before = self.local_time(t - 1).offset.into();
+                result[2 * i + j] = (t, before, self.local_time(t).offset.into());
+            }
+        }
+        result.sort_unstable();
+        Some(result)
+    }
+
+    /// The first transition after `seconds_since_epoch`
+    pub(crate) fn next_transition(&self, seconds_since_epoch: i64) -> Option<(i64, i64, i64)> {
+        self.transitions_around(seconds_since_epoch)?
+            .into_iter()
+            .find(|t| t.0 > seconds_since_epoch && t.1 != t.2)
+    }
+
+    /// The last transition before `seconds_since_epoch`
+    pub(crate) fn previous_transition(&self, seconds_since_epoch: i64) -> Option<(i64, i64, i64)> {
+        self.transitions_around(seconds_since_epoch)?
+            .into_iter()
+            .rev()
+            .find(|t| t.0 < seconds_since_epoch && t.1 != t.2)
+    }
+
+    pub(crate) fn local_time(&self, seconds_since_epoch: i64) -> LocalTimeType<'_> {
+        match &self.dst {
+            Some(dst) if self.is_dst(seconds_since_epoch) => LocalTimeType {
+                offset: dst.offset,
+                std_offset: self.std_offset,
+                is_dst: true,
+                name: dst.name.as_ref(),
+            },
+            _ => LocalTimeType {
+                offset: self.std_offset,
+                std_offset: self.std_offset,
+                is_dst: false,
+                name: self.std_name.as_ref(),
+            },
+        }
+    }
+
+    pub(crate) fn into_owned(self) -> PosixTimeZone {
+        PosixTimeZone {
+            std_name: self.std_name.as_ref().to_string(),
+            std_offset: self.std_offset,
+            dst: self.dst.map(|dst| PosixDst {
+                name: dst.name.as_ref().to_string(),
+                offset: dst.offset,
+                start: dst.start,
+                end: dst.end,
+            }),
+        }
+    }
+}
+
+impl<'a> PosixTimeZone<&'a str> {
+    pub(crate) fn parse(s: &'a str) -> Option<Self> {
+        let mut p = Parser(s.as_bytes());
+        let std_name = p.name()?;
+        let std_offset = -p.offset(24)?;
+        if p.0.is_empty() {
+            return Some(Self {
+                std_name,
+                std_offset,
+                dst: None,
+            });
+        }
+        let dst_name = p.name()?;
+        let dst_offset = match p.0.first() {
+            Some(b',') | None => std_offset + 3600,
+            Some(_) => -p.offset(24)?,
+        };
+        let (start, end) = if p.0.is_empty() {
+            // The default rule of the POSIX standard, which is the US rule
+            let start = PosixDate::MonthWeekDay {
+                month: 3,
+                week: 2,
+                day: 0,
+            };
+            let end = PosixDate::MonthWeekDay {
+                month: 11,
+                week: 1,
+                day: 0,
+            };
+            (
+                PosixTransition {
+                    date: start,
+                    time: 7200,
+                },
+                PosixTransition {
+                    date: end,
+                    time: 7200,
+                },
+            )
+        } else {
+            p.eat(b',')?;
+            let start = p.transition()?;
+            p.eat(b',')?;
+            let end = p.transition()?;
+            (start, end)
+        };
+        if !p.0.is_empty() {
+            return None;
+        }
+        Some(Self {
+            std_name,
+            std_offset,
+            dst: Some(PosixDst {
+                name: dst_name,
+                offset: dst_offset,
+                start,
+                end,
+            }),
+        })
+    }
+}
+
+impl TimeZone {
+    /// Creates a time zone following a POSIX TZ string, like `CET-1CEST,M3.5.0,M10.5.0/3`,
+    /// which is also its id
+    pub fn from_posix_tz(s: &str) -> Result<TimeZone, ParseError> {
+        let rule = PosixTimeZone::parse(s).ok_or_else(|| {
+            ParseError::new(ParseErrorKind::UnknownTimeZone(s.to_string()), 0)
+                .with_expected("a POSIX TZ string")
+        })?;
+        let tzif = Tzif::from_posix(s.to_string(), rule.into_owned());
+        Ok(TimeZone(TimezoneInner::Tzif(Arc::new(tzif))))
+    }
+}
+
+struct Parser<'a>(&'a [u8]);
+
+impl<'a> Parser<'a> {
+    fn eat(&mut self, c: u8) -> Option<()> {
+        let (first, rest) = self.0.split_first()?;
+        if *first != c {
+            return None;
+        }
+        self.0 = rest;
+        Some(())
+    }
+
+    fn take_while(&mut self, f: impl Fn(u8) -> bool) -> &'a [u8] {
+        let len = self.0.iter().take_while(|c| f(**c)).count();
+        let (result, rest) = self.0.split_at(len);
+        self.0 = rest;
+        result
+    }
+
+    fn name(&mut self) -> Option<&'a str> {
+        let name = if self.eat(b'<').is_some() {
+            let name = self.take_while(|c| c.is_ascii_alphanumeric() || c == b'+' || c == b'-');
+            self.eat(b'>')?;
+            name
+        } else {
+            self.take_while(|c| c.is_ascii_alphabetic())
+        };
+        // Only ascii was taken
+        let name = core::str::from_utf8(name).ok()?;
+        if name.len() < 3 {
+            return None;
+        }
+        Some(name)
+    }
+
+    fn number(&mut self, max: u32) -> Option<u32> {
+        let digits = self.take_while(|c| c.is_ascii_digit());
+        if digits.is_empty() || digits.len() > 3 {
+            return None;
+        }
+        let r = core::str::from_utf8(digits).ok()?.parse().ok()?;
+        (r <= max).then_some(r)
+    }
+
+    /// Parses `[+-]hh[:mm[:ss]]` into seconds, in the direction written
+    fn offset(&mut self, max_hours: u32) -> Option<i32> {
+        let is_neg = match self.0.first() {
+            Some(b'-') => true,
+            Some(b'+') => false,
+            _ => {
+                return self.unsigned_time(max_hours);
+            }
+        };
+        self.0 = &self.0[1..];
+        let r = self.unsigned_time(max_hours)?;
+        Some(if is_neg { -r } else { r })
+    }
+
+    fn unsigned_time(&mut self, max_hours: u32) -> Option<i32> {
+        let mut r = self.number(max_hours)? * 3600;
+        if self.eat(b':').is_some() {
+            r += self.number(59)? * 60;
+            if self.eat(b':').is_some() {
+                r += self.number(59)?;
+            }
+        }
+        Some(r as i32)
+    }
+
+    fn transition(&mut self) -> Option<PosixTransition> {
+        let date = if self.eat(b'J').is_some() {
+            let n = self.number(365)?;
+            if n == 0 {
+                return None;
+            }
+            PosixDate::JulianNoLeap(n as u16)
+        } else if self.eat(b'M').is_some() {
+            let month = self.number(12)? as u8;
+            self.eat(b'.')?;
+            let week = self.number(5)? as u8;
+            self.eat(b'.')?;
+            let day = self.number(6)? as u8;
+            if month == 0 || week == 0 {
+                return None;
+            }
+            PosixDate::MonthWeekDay { month, week, day }
+        } else {
+            PosixDate::Julian(self.number(365)? as u16)
+        };
+        let time = if self.eat(b'/').is_some() {
+            self.offset(167)?
+        } else {
+            7200
+        };
+        Some(PosixTransition { date, time })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PosixTimeZone;
+    use crate::iso::IsoDate;
+
+    fn epoch(year: i32, month: u8, day: u8, hour: i64) -> i64 {
+        IsoDate::new(year, month, day).unwrap().to_epoch_second() + hour * 3600
+    }
+
+    #[test]
+    fn central_europe() {
+        let tz = PosixTimeZone::parse("CET-1CEST,M3.5.0,M10.5.0/3").unwrap();
+        assert_eq!(tz.std_offset, 3600);
+        // 2023-03-26 01:00 UTC is the start of CEST
+        assert_eq!(tz.local_time(epoch(2023, 3, 26, 1) - 1).offset, 3600);
+        assert_eq!(tz.local_time(epoch(2023, 3, 26, 1)).offset, 7200);
+        assert_eq!(tz.local_time(epoch(2023, 3, 26, 1)).name, "CEST");
+        // 2023-10-29 01:00 UTC is the end of CEST
+        assert_eq!(tz.local_time(epoch(2023, 10, 29, 1) - 1).offset, 7200);
+        assert_eq!(tz.local_time(epoch(2023, 10, 29, 1)).offset, 3600);
+    }
+
+    #[test]
+    fn southern_hemisphere() {
+        let tz = PosixTimeZone::parse("AEST-10AEDT,M10.1.0,M4.1.0/3").unwrap();
+        assert!(tz.local_time(epoch(2023, 1, 15, 0)).is_dst);
+        assert!(!tz.local_time(epoch(2023, 6, 15, 0)).is_dst);
+        assert!(tz.local_time(epoch(2023, 12, 31, 23)).is_dst);
+    }
+
+    #[test]
+    fn quoted_names_and_extensions() {
+        let tz = PosixTimeZone::parse("<+0330>-3:30").unwrap();
+        assert_eq!(tz.std_name, "+0330");
+        assert_eq!(tz.std_offset, 12600);
+        assert!(tz.dst.is_none());
+        // Permanent daylight saving time, from RFC 8536
+        let tz = PosixTimeZone::parse("EST5EDT,0/0,J365/25").unwrap();
+        assert!(tz.local_time(epoch(2023, 1, 1, 6)).is_dst);
+        assert!(tz.local_time(epoch(2023, 7, 1, 0)).is_dst);
+        let tz = PosixTimeZone::parse("<-03>3<-02>,M3.5.0/-2,M10.5.0/-1").unwrap();
+        assert_eq!(tz.dst.unwrap().start.time, -7200);
+    }
+
+    #[test]
+    fn invalid() {
+        for s in [
+            "",
+            "AB1",
+            "EST",
+            "EST5EDT,M3.2.0",
+            "EST5EDT,M13.2.0,M11.1.0",
+            "EST5x",
+        ] {
+            assert_eq!(PosixTimeZone::parse(s), None, "{}", s);
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Detecting the local time zone of the system, the way the C library does
+
+use std::{path::Path, str::FromStr};
+
+use super::{iana_generated::Tz, TimeZone, TimezoneInner};
+
+/// Returns the zone name of a path inside a zoneinfo directory, like
+/// `/usr/share/zoneinfo/Europe/Berlin`
+fn zone_name_of_path(path: &str) -> Option<&str> {
+    let (_, name) = path.rsplit_once("zoneinfo/")?;
+    let name = name
+        .strip_prefix("posix/")
+        .or_else(|| name.strip_prefix("right/"))
+        .unwrap_or(name);
+    Some(name)
+}
+
+impl TimeZone {
+    /// Returns the local time zone of the system
+    ///
+    /// The `TZ` environment variable takes precedence, and may be an IANA name, the path of
+    /// a TZif file prefixed by `:`, or a POSIX TZ string like `CET-1CEST,M3.5.0,M10.5.0/3`.
+    /// Otherwise the zone is found from `/etc/localtime`. Links are resolved to their
+    /// canonical zone, when the name is known.
+    ///
+    /// If no zone can be found, or `TZ` is invalid, this is UTC, like in the C library.
+    pub fn system() -> TimeZone {
+        match std::env::var("TZ") {
+            Ok(tz) if !tz.is_empty() => Self::from_tz_var(&tz, &Self::zoneinfo_dir()),
+            _ => Self::from_localtime(),
+        }
+        .unwrap_or(Self::UTC)
+    }
+
+    fn builtin(name: &str) -> Option<TimeZone> {
+        let tz = Tz::from_str(name).ok()?;
+        Some(Self(TimezoneInner::Tz(tz.canonical())))
+    }
+
+    /// Finds the zone of the `TZ` variable, looking up zone names in the given zoneinfo
+    /// directory
+    fn from_tz_var(tz: &str, zoneinfo_dir: &Path) -> Option<TimeZone> {
+        let (name, is_posix) = match tz.strip_prefix(':') {
+            Some(name) => (name, false),
+            None => (tz, true),
+        };
+        if name.starts_with('/') {
+            return Self::from_path(Path::new(name));
+        }
+        if let Some(r) =
+            Self::builtin(name).or_else(|| Self::from_zoneinfo_dir(zoneinfo_dir, name).ok())
+        {
+            return Some(r);
+        }
+        if !is_posix {
+            return None;
+        }
+        Self::from_posix_tz(tz).ok()
+    }
+
+    fn from_path(path: &Path) -> Option<TimeZone> {
+        let path = path.to_str()?;
+        if let Some(r) = zone_name_of_path(path).and_then(Self::builtin) {
+            return Some(r);
+        }
+        let name = zone_name_of_path(path).unwrap_or(path);
+        Self::from_tzif(name, &std::fs::read(path).ok()?).ok()
+    }
+
+    fn from_localtime() -> Option<TimeZone> {
+        let localtime = Path::new("/etc/localtime");
+        if let Ok(target) = std::fs::read_link(localtime) {
+            // Relative targets are relative to `/etc`
+            return Self::from_path(&Path::new("/etc").join(target));
+        }
+        // A copy of the zone file, whose name may be in `/etc/timezone` on Debian
+        if let Ok(name) = std::fs::read_to_string("/etc/timezone") {
+            if let Some(r) = Self::builtin(name.trim()) {
+                return Some(r);
+            }
+        }
+        Self::from_path(localtime)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::zone_name_of_path;
+    use crate::{timezone::tzif::tests::build, TimeZone, TimeZoneProtocol};
+
+    #[test]
+    fn zone_names_of_paths() {
+        assert_eq!(
+            zone_name_of_path("../usr/share/zoneinfo/Europe/Berlin"),
+            Some("Europe/Berlin")
+        );
+        assert_eq!(
+            zone_name_of_path("/usr/share/zoneinfo/posix/Asia/Tehran"),
+            Some("Asia/Tehran")
+        );
+        assert_eq!(zone_name_of_path("/etc/localtime"), None);
+    }
+
+    #[test]
+    fn tz_variable() {
+        let tz_dir = std::env::temp_dir()
+            .join(format!("temporal-core-{}", std::process::id()))
+            .join("zoneinfo");
+        std::fs::create_dir_all(tz_dir.join("Acme")).unwrap();
+        let path = tz_dir.join("Acme/Zone");
+        std::fs::write(&path, build(0, &[], &[(10800, false, "XST")], "")).unwrap();
+        let tz = TimeZone::from_tz_var(&format!(":{}", path.display()), &tz_dir).unwrap();
+        assert_eq!(tz.id(), "Acme/Zone");
+        assert_eq!(tz.get_second_offset(0), 3 * 3600);
+        let tz = TimeZone::from_tz_var("Acme/Zone", &tz_dir);
+        assert_eq!(tz.unwrap().id(), "Acme/Zone");
+        #[cfg(not(feature = "drop-backward-links"))]
+        {
+            let tz = TimeZone::from_tz_var("US/Eastern", &tz_dir).unwrap();
+            assert_eq!(tz.id(), "America/New_York");
+        }
+        let tz = TimeZone::from_tz_var("XST-3XDT,M3.5.0,M10.5.0/3", &tz_dir).unwrap();
+        assert_eq!(tz.id(), "XST-3XDT,M3.5.0,M10.5.0/3");
+        assert_eq!(tz.get_second_offset(0), 3 * 3600);
+        assert!(TimeZone::from_tz_var(":XST-3", &tz_dir).is_none());
+        assert!(TimeZone::from_tz_var("Not/A_Zone", &tz_dir).is_none());
+        std::fs::remove_dir_all(tz_dir.parent().unwrap()).unwrap();
+    }
+}
@@ -1,3 +1,9 @@
+use super::{
+    iana_generated::{CHECKPOINT_INTERVAL, TIMESPANS},
+    posix::PosixTimeZone,
+    transition::{next_in_table, partition_point, previous_in_table},
+};
+
 /// An Offset that applies for a period of time
 ///
 /// For example, [`::US::Eastern`] is composed of at least two
@@ -21,28 +27,118 @@ impl FixedTimespan {
 #[derive(Copy, Clone)]
 pub struct FixedTimespanSet {
     pub first: FixedTimespan,
-    pub rest: &'static [(i64, FixedTimespan)],
+    /// Seconds since the unix epoch of every [`CHECKPOINT_INTERVAL`]th transition to other
+    /// timespans, starting with the first one
+    pub checkpoints: &'static [i64],
+    /// Seconds from the last checkpoint to each transition
+    pub offsets: &'static [u32],
+    /// Index in [`TIMESPANS`] of the timespan starting at each transition
+    pub spans: &'static [u16],
+    /// Rule recurring after the last transition, generated from the POSIX TZ string of
+    /// the zone
+    pub rule: PosixTimeZone<&'static str>,
 }
 
 impl FixedTimespanSet {
-    pub(super) fn select_with_sec(&self, sec: i64) -> FixedTimespan {
-        if self.rest.is_empty() || sec < self.rest[0].0 {
-            return self.first;
+    fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Start of the `i`th transition
+    fn time(&self, i: usize) -> i64 {
+        self.checkpoints[i / CHECKPOINT_INTERVAL] + i64::from(self.offsets[i])
+    }
+
+    /// Start and offset of the timespan of the `i`th transition
+    fn entry(&self, i: usize) -> (i64, i64) {
+        (
+            self.time(i),
+            TIMESPANS[self.spans[i] as usize].second_offset(),
+        )
+    }
+
+    /// Evaluates the recurring rule of the zone
+    fn select_with_rule(&self, sec: i64) -> FixedTimespan {
+        let rule = &self.rule;
+        match rule.dst {
+            Some(dst) if rule.is_dst(sec) => FixedTimespan {
+                utc_offset: rule.std_offset,
+                dst_offset: dst.offset - rule.std_offset,
+                name: dst.name,
+            },
+            _ => FixedTimespan {
+                utc_offset: rule.std_offset,
+                dst_offset: 0,
+                name: rule.std_name,
+            },
         }
-        let mut l = 0;
-        let mut r = self.rest.len();
-        while r - l > 1 {
-            let mid = (l + r) / 2;
-            if sec < self.rest[mid].0 {
-                r = mid;
-            } else {
-                l = mid;
-            }
+    }
+
+    pub(super) fn next_transition(&self, sec: i64) -> Option<(i64, i64, i64)> {
+        next_in_table(
+            self.first.second_offset(),
+            self.len(),
+            |i| self.entry(i),
+            Some(&self.rule),
+            sec,
+        )
+    }
+
+    pub(super) fn previous_transition(&self, sec: i64) -> Option<(i64, i64, i64)> {
+        previous_in_table(
+            self.first.second_offset(),
+            self.len(),
+            |i| self.entry(i),
+            Some(&self.rule),
+            sec,
+        )
+    }
+
+    pub(super) fn select_with_sec(&self, sec: i64) -> FixedTimespan {
+        match partition_point(self.len(), |i| self.time(i) <= sec) {
+            0 => self.first,
+            n if n == self.len() => self.select_with_rule(sec),
+            n => TIMESPANS[self.spans[n - 1] as usize],
         }
-        self.rest[l].1
     }
 }
 
 pub trait TimeSpans {
     fn timespans(&self) -> FixedTimespanSet;
 }
+
+#[cfg(test)]
+mod tests {
+    use std::mem::{size_of, size_of_val};
+
+    use super::{FixedTimespan, TimeSpans, TIMESPANS};
+    use crate::timezone::iana_generated::TZ_VARIANTS;
+
+    #[test]
+    fn compact_size() {
+        // Compared to a table of `(i64, FixedTimespan)` per zone, as generated before
+        let mut compact = size_of_val(&TIMESPANS);
+        let mut plain = 0;
+        for tz in TZ_VARIANTS.iter().filter(|tz| tz.canonical() == **tz) {
+            let set = tz.timespans();
+            compact +=
+                size_of_val(set.checkpoints) + size_of_val(set.offsets) + size_of_val(set.spans);
+            plain += set.len() * size_of::<(i64, FixedTimespan)>();
+        }
+        assert!(
+            compact * 3 < plain,
+            "{} bytes instead of {}",
+            compact,
+            plain
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "drop-pre-1970")]
+    fn without_pre_1970_transitions() {
+        for tz in TZ_VARIANTS {
+            let set = tz.timespans();
+            assert!(set.len() == 0 || set.time(0) > 0, "{:?}", tz);
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! Finding the instants where the offset of a time zone changes
+
+use super::{posix::PosixTimeZone, timezone_impl::TimeSpans, TimeZone, TimezoneInner};
+use crate::SignedDuration;
+
+/// An instant where the offset of a time zone changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeZoneTransition {
+    pub epoch: SignedDuration,
+    /// Offset in seconds before the transition
+    pub offset_before: i64,
+    /// Offset in seconds from the transition on
+    pub offset_after: i64,
+}
+
+/// Number of indices below `len` where `pred` holds, which must be the indices before
+/// some point, like [`slice::partition_point`]
+pub(super) fn partition_point(len: usize, pred: impl Fn(usize) -> bool) -> usize {
+    let (mut low, mut high) = (0, len);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if pred(mid) {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    low
+}
+
+/// Finds the first transition after `sec` in a table of `len` transitions, where `entry`
+/// gives the time and the offset after each one, followed by a recurring rule. Without any
+/// transition in the table, the rule applies at all times.
+pub(super) fn next_in_table<S: AsRef<str>>(
+    first: i64,
+    len: usize,
+    entry: impl Fn(usize) -> (i64, i64),
+    rule: Option<&PosixTimeZone<S>>,
+    sec: i64,
+) -> Option<(i64, i64, i64)> {
+    let start = partition_point(len, |i| entry(i).0 <= sec);
+    let mut before = match start {
+        0 => first,
+        _ => entry(start - 1).1,
+    };
+    for i in start..len {
+        let (time, after) = entry(i);
+        if before != after {
+            return Some((time, before, after));
+        }
+        before = after;
+    }
+    let from = match len {
+        0 => sec,
+        _ => sec.max(entry(len - 1).0),
+    };
+    rule?.next_transition(from)
+}
+
+/// Finds the last transition before `sec`, like [`next_in_table`]
+pub(super) fn previous_in_table<S: AsRef<str>>(
+    first: i64,
+    len: usize,
+    entry: impl Fn(usize) -> (i64, i64),
+    rule: Option<&PosixTimeZone<S>>,
+    sec: i64,
+) -> Option<(i64, i64, i64)> {
+    let last = match len {
+        0 => i64::MIN,
+        _ => entry(len - 1).0,
+    };
+    // The rule only applies after the table
+    if sec > last {
+        if let Some(t) = rule.and_then(|rule| rule.previous_transition(sec)) {
+            if t.0 > last {
+                return Some(t);
+            }
+        }
+    }
+    let end = partition_point(len, |i| entry(i).0 < sec);
+    (0..end).rev().find_map(|i| {
+        let (time, after) = entry(i);
+        let before = match i {
+            0 => first,
+            _ => entry(i - 1).1,
+        };
+        (before != after).then_some((time, before, after))
+    })
+}
+
+fn to_transition((sec, offset_before, offset_after): (i64, i64, i64)) -> TimeZoneTransition {
+    TimeZoneTransition {
+        epoch: SignedDuration::from_secs(sec),
+        offset_before,
+        offset_after,
+    }
+}
+
+impl TimeZone {
+    /// Returns the first instant strictly after `after` where the offset changes, or `None`
+    /// if it never changes again
+    pub fn next_transition(&self, after: SignedDuration) -> Option<TimeZoneTransition> {
+        let sec = after.as_secs();
+        match &self.0 {
+            TimezoneInner::Tz(x) => x.timespans().next_transition(sec),
+            TimezoneInner::Fixed(_) => None,
+            TimezoneInner::Tzif(x) => x.next_transition(sec),
+            TimezoneInner::Custom(x) => return x.0.next_transition(after),
+        }
+        .map(to_transition)
+    }
+
+    /// Returns the last instant strictly before `before` where the offset changed, or `None`
+    /// if it never changed before
+    pub fn previous_transition(&self, before: SignedDuration) -> Option<TimeZoneTransition> {
+        // Transitions are at whole seconds
+        let sec = before.as_secs() + i64::from(before.subsec_nanos() > 0);
+        match &self.0 {
+            TimezoneInner::Tz(x) => x.timespans().previous_transition(sec),
+            TimezoneInner::Fixed(_) => None,
+            TimezoneInner::Tzif(x) => x.previous_transition(sec),
+            TimezoneInner::Custom(x) => return x.0.previous_transition(before),
+        }
+        .map(to_transition)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{IsoDate, SignedDuration, TimeZone};
+
+    fn epoch(year: i32, month: u8, day: u8, hour: i64) -> SignedDuration {
+        SignedDuration::from_secs(
+            IsoDate::new(year, month, day).unwrap().to_epoch_second() + hour * 3600,
+        )
+    }
+
+    #[test]
+    fn next_and_previous() {
+        let tz: TimeZone = "America/New_York".parse().unwrap();
+        // 2023-03-12 07:00 UTC is the start of EDT
+        let t = tz.next_transition(epoch(2023, 1, 1, 0)).unwrap();
+        assert_eq!(t.epoch, epoch(2023, 3, 12, 7));
+        assert_eq!((t.offset_before, t.offset_after), (-5 * 3600, -4 * 3600));
+        let t = tz.next_transition(t.epoch).unwrap();
+        assert_eq!(t.epoch, epoch(2023, 11, 5, 6));
+        assert_eq!(
+            tz.previous_transition(t.epoch).unwrap().epoch,
+            epoch(2023, 3, 12, 7)
+        );
+        let after = SignedDuration::new(t.epoch.as_secs(), 1);
+        assert_eq!(tz.previous_transition(after).unwrap().epoch, t.epoch);
+    }
+
+    #[test]
+    fn beyond_the_table() {
+        let tz: TimeZone = "Europe/Berlin".parse().unwrap();
+        let t = tz.next_transition(epoch(2150, 5, 1, 0)).unwrap();
+        assert_eq!(t.epoch, epoch(2150, 10, 25, 1));
+        let t = tz.previous_transition(epoch(2150, 5, 1, 0)).unwrap();
+        assert_eq!(t.epoch, epoch(2150, 3, 29, 1));
+        assert_eq!((t.offset_before, t.offset_after), (3600, 7200));
+    }
+
+    #[test]
+    fn without_transitions() {
+        let tz: TimeZone = "+03:30".parse().unwrap();
+        assert_eq!(tz.next_transition(epoch(2000, 1, 1, 0)), None);
+        let tz = TimeZone::from_posix_tz("EST5EDT,0/0,J365/25").unwrap();
+        assert_eq!(tz.next_transition(epoch(2000, 1, 1, 0)), None);
+        let tz = TimeZone::from_posix_tz("<-03>3<-02>,M3.5.0/-2,M10.5.0/-1").unwrap();
+        let t = tz.previous_transition(epoch(2000, 1, 1, 0)).unwrap();
+        assert_eq!((t.offset_before, t.offset_after), (-2 * 3600, -3 * 3600));
+    }
+}
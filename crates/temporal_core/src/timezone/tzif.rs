@@ -0,0 +1,538 @@
+//! Time zones loaded at runtime from TZif files, as described in RFC 8536
+//!
+//! Transitions after the last one stored in the file are computed from the POSIX TZ
+//! string in the footer of version 2 and later files.
+
+use alloc::{
+    string::{String, ToString},
+    sync::Arc,
+    vec,
+    vec::Vec,
+};
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+
+use super::{
+    posix::PosixTimeZone,
+    transition::{next_in_table, previous_in_table},
+    LocalTimeType, TimeZone, TimezoneInner,
+};
+
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum TzifError {
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    /// The name passed to [`TimeZone::from_zoneinfo`] is not a relative path inside
+    /// the zoneinfo directory
+    InvalidName(String),
+    /// The data doesn't start with the `TZif` magic
+    NotTzif,
+    UnsupportedVersion(u8),
+    /// The data is truncated or inconsistent
+    Malformed,
+    /// The footer is not a valid POSIX TZ string
+    InvalidFooter(String),
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for TzifError {
+    fn from(e: std::io::Error) -> Self {
+        TzifError::Io(e)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TzifType {
+    offset: i32,
+    is_dst: bool,
+    name: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Tzif {
+    pub(crate) id: String,
+    /// Transition times, ascending, and the index of the local time type starting at them
+    transitions: Vec<(i64, u8)>,
+    types: Vec<TzifType>,
+    footer: Option<PosixTimeZone>,
+}
+
+struct Header {
+    version: u8,
+    isutcnt: usize,
+    isstdcnt: usize,
+    leapcnt: usize,
+    timecnt: usize,
+    typecnt: usize,
+    charcnt: usize,
+}
+
+impl Header {
+    /// Length of the data block following this header, with 4 byte times for version 1
+    /// and 8 byte times for the rest
+    fn block_len(&self, time_size: usize) -> usize {
+        self.timecnt * (time_size + 1)
+            + self.typecnt * 6
+            + self.charcnt
+            + self.leapcnt * (time_size + 4)
+            + self.isstdcnt
+            + self.isutcnt
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], TzifError> {
+        if self.0.len() < len {
+            return Err(TzifError::Malformed);
+        }
+        let (result, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(result)
+    }
+
+    fn u8(&mut self) -> Result<u8, TzifError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn i32(&mut self) -> Result<i32, TzifError> {
+        Ok(i32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn i64(&mut self) -> Result<i64, TzifError> {
+        Ok(i64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn time(&mut self, time_size: usize) -> Result<i64, TzifError> {
+        if time_size == 4 {
+            self.i32().map(i64::from)
+        } else {
+            self.i64()
+        }
+    }
+
+    fn count(&mut self) -> Result<usize, TzifError> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()) as usize)
+    }
+
+    fn header(&mut self) -> Result<Header, TzifError> {
+        if self.take(4).map_err(|_| TzifError::NotTzif)? != b"TZif" {
+            return Err(TzifError::NotTzif);
+        }
+        let version = match self.u8()? {
+            0 => 1,
+            v @ b'2'..=b'4' => v - b'0',
+            v => return Err(TzifError::UnsupportedVersion(v)),
+        };
+        self.take(15)?;
+        Ok(Header {
+            version,
+            isutcnt: self.count()?,
+            isstdcnt: self.count()?,
+            leapcnt: self.count()?,
+            timecnt: self.count()?,
+            typecnt: self.count()?,
+            charcnt: self.count()?,
+        })
+    }
+}
+
+impl Tzif {
+    pub(crate) fn parse(id: String, data: &[u8]) -> Result<Self, TzifError> {
+        let mut r = Reader(data);
+        let mut header = r.header()?;
+        let mut time_size = 4;
+        if header.version >= 2 {
+            // The version 1 block is only there for old readers
+            r.take(header.block_len(4))?;
+            header = r.header()?;
+            time_size = 8;
+        }
+        if header.typecnt == 0 || header.typecnt > 256 || header.charcnt == 0 {
+            return Err(TzifError::Malformed);
+        }
+        let mut times = Vec::with_capacity(header.timecnt);
+        for _ in 0..header.timecnt {
+            times.push(r.time(time_size)?);
+        }
+        if times.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(TzifError::Malformed);
+        }
+        let indices = r.take(header.timecnt)?;
+        if indices.iter().any(|&i| i as usize >= header.typecnt) {
+            return Err(TzifError::Malformed);
+        }
+        let mut raw_types = Vec::with_capacity(header.typecnt);
+        for _ in 0..header.typecnt {
+            let offset = r.i32()?;
+            let is_dst = match r.u8()? {
+                0 => false,
+                1 => true,
+                _ => return Err(TzifError::Malformed),
+            };
+            raw_types.push((offset, is_dst, r.u8()? as usize));
+        }
+        let chars = r.take(header.charcnt)?;
+        let types = raw_types
+            .into_iter()
+            .map(|(offset, is_dst, index)| {
+                let name = chars.get(index..).ok_or(TzifError::Malformed)?;
+                let len = name
+                    .iter()
+                    .position(|&c| c == 0)
+                    .ok_or(TzifError::Malformed)?;
+                let name =
+                    String::from_utf8(name[..len].to_vec()).map_err(|_| TzifError::Malformed)?;
+                Ok(TzifType {
+                    offset,
+                    is_dst,
+                    name,
+                })
+            })
+            .collect::<Result<Vec<_>, TzifError>>()?;
+        let mut leap_seconds = Vec::with_capacity(header.leapcnt);
+        for _ in 0..header.leapcnt {
+            leap_seconds.push((r.time(time_size)?, r.i32()?));
+        }
+        // The standard and UT indicators only matter for POSIX TZ strings without rules,
+        // which we don't support.
+        r.take(header.isstdcnt + header.isutcnt)?;
+        let footer = if header.version >= 2 {
+            let rest = r.0;
+            if rest.first() != Some(&b'\n') {
+                return Err(TzifError::Malformed);
+            }
+            let len = rest[1..]
+                .iter()
+                .position(|&c| c == b'\n')
+                .ok_or(TzifError::Malformed)?;
+            let footer =
+                core::str::from_utf8(&rest[1..len + 1]).map_err(|_| TzifError::Malformed)?;
+            if footer.is_empty() {
+                None
+            } else {
+                Some(
+                    PosixTimeZone::parse(footer)
+                        .ok_or_else(|| TzifError::InvalidFooter(footer.to_string()))?
+                        .into_owned(),
+                )
+            }
+        } else {
+            None
+        };
+        // Files of the `right/` hierarchy count leap seconds in their transition times,
+        // which we ignore like the rest of Temporal.
+        let transitions = times
+            .into_iter()
+            .zip(indices.iter().copied())
+            .map(|(time, index)| {
+                let correction = leap_seconds
+                    .iter()
+                    .take_while(|(occurrence, _)| *occurrence <= time)
+                    .last()
+                    .map_or(0, |(_, correction)| *correction);
+                (time - correction as i64, index)
+            })
+            .collect();
+        Ok(Self {
+            id,
+            transitions,
+            types,
+            footer,
+        })
+    }
+
+    /// A zone without transitions, which follows a POSIX TZ string at all times
+    pub(crate) fn from_posix(id: String, rule: PosixTimeZone) -> Self {
+        let standard = TzifType {
+            offset: rule.std_offset,
+            is_dst: false,
+            name: rule.std_name.clone(),
+        };
+        Self {
+            id,
+            transitions: vec![],
+            types: vec![standard],
+            footer: Some(rule),
+        }
+    }
+
+    /// Start and offset of the `i`th transition
+    fn entry(&self, i: usize) -> (i64, i64) {
+        let (time, index) = self.transitions[i];
+        (time, self.types[index as usize].offset.into())
+    }
+
+    fn first_offset(&self) -> i64 {
+        self.types[0].offset.into()
+    }
+
+    pub(crate) fn next_transition(&self, sec: i64) -> Option<(i64, i64, i64)> {
+        next_in_table(
+            self.first_offset(),
+            self.transitions.len(),
+            |i| self.entry(i),
+            self.footer.as_ref(),
+            sec,
+        )
+    }
+
+    pub(crate) fn previous_transition(&self, sec: i64) -> Option<(i64, i64, i64)> {
+        previous_in_table(
+            self.first_offset(),
+            self.transitions.len(),
+            |i| self.entry(i),
+            self.footer.as_ref(),
+            sec,
+        )
+    }
+
+    pub(crate) fn local_time_type(&self, seconds_since_epoch: i64) -> LocalTimeType<'_> {
+        let position = match self.transitions.last() {
+            Some(&(last, _)) if seconds_since_epoch >= last && self.footer.is_some() => {
+                return self
+                    .footer
+                    .as_ref()
+                    .unwrap()
+                    .local_time(seconds_since_epoch);
+            }
+            Some(_) => self
+                .transitions
+                .partition_point(|&(time, _)| time <= seconds_since_epoch)
+                .checked_sub(1),
+            None => match &self.footer {
+                Some(footer) => return footer.local_time(seconds_since_epoch),
+                None => None,
+            },
+        };
+        let t = &self.types[position.map_or(0, |i| self.transitions[i].1 as usize)];
+        LocalTimeType {
+            offset: t.offset,
+            std_offset: if t.is_dst {
+                self.standard_offset_around(position)
+                    .unwrap_or(t.offset - 3600)
+            } else {
+                t.offset
+            },
+            is_dst: t.is_dst,
+            name: &t.name,
+        }
+    }
+
+    /// TZif files don't record the standard offset of daylight saving types, so it is
+    /// taken from the closest standard type in effect before, or else after, the
+    /// transition at `position`.
+    fn standard_offset_around(&self, position: Option<usize>) -> Option<i32> {
+        let (before, after) = match position {
+            Some(i) => (&self.transitions[..i], &self.transitions[i + 1..]),
+            None => (&[][..], &self.transitions[..]),
+        };
+        before
+            .iter()
+            .rev()
+            .chain(after)
+            .map(|&(_, index)| &self.types[index as usize])
+            .find(|t| !t.is_dst)
+            .map(|t| t.offset)
+    }
+}
+
+impl TimeZone {
+    /// Creates a time zone from the contents of a TZif file, with the given id
+    pub fn from_tzif(id: &str, data: &[u8]) -> Result<TimeZone, TzifError> {
+        let tzif = Tzif::parse(id.to_string(), data)?;
+        Ok(TimeZone(TimezoneInner::Tzif(Arc::new(tzif))))
+    }
+
+    /// Loads a time zone, like `Europe/Berlin`, from the zoneinfo directory of the system,
+    /// which is `$TZDIR` or else `/usr/share/zoneinfo`
+    ///
+    /// Unlike parsing a `TimeZone`, this gives the zone data installed on the system, which
+    /// may be more recent than the data compiled in this crate.
+    #[cfg(feature = "std")]
+    pub fn from_zoneinfo(name: &str) -> Result<TimeZone, TzifError> {
+        Self::from_zoneinfo_dir(&Self::zoneinfo_dir(), name)
+    }
+
+    /// The zoneinfo directory of the system, which is `$TZDIR` or else
+    /// `/usr/share/zoneinfo`
+    #[cfg(feature = "std")]
+    pub(super) fn zoneinfo_dir() -> PathBuf {
+        std::env::var_os("TZDIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("/usr/share/zoneinfo"))
+    }
+
+    /// Loads a time zone from the given zoneinfo directory
+    #[cfg(feature = "std")]
+    pub(super) fn from_zoneinfo_dir(dir: &Path, name: &str) -> Result<TimeZone, TzifError> {
+        let is_valid = !name.is_empty()
+            && !name.starts_with('/')
+            && name
+                .split('/')
+                .all(|part| !part.is_empty() && part != "." && part != "..");
+        if !is_valid {
+            return Err(TzifError::InvalidName(name.to_string()));
+        }
+        Self::from_tzif(name, &std::fs::read(dir.join(name))?)
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::TzifError;
+    use crate::{IsoDate, IsoTime, TimeZone, TimeZoneProtocol};
+
+    /// Builds a TZif file with a version 1 block, and a version 2 block with the footer
+    pub(crate) fn build(
+        version: u8,
+        transitions: &[(i64, u8)],
+        types: &[(i32, bool, &str)],
+        footer: &str,
+    ) -> Vec<u8> {
+        let mut chars = vec![];
+        let mut indices = vec![];
+        for (_, _, name) in types {
+            indices.push(chars.len() as u8);
+            chars.extend_from_slice(name.as_bytes());
+            chars.push(0);
+        }
+        let block = |time_size: usize| {
+            let mut r = b"TZif".to_vec();
+            r.push(version);
+            r.extend_from_slice(&[0; 15]);
+            for count in [0, 0, 0, transitions.len(), types.len(), chars.len()] {
+                r.extend_from_slice(&(count as u32).to_be_bytes());
+            }
+            for (time, _) in transitions {
+                if time_size == 4 {
+                    r.extend_from_slice(&(*time as i32).to_be_bytes());
+                } else {
+                    r.extend_from_slice(&time.to_be_bytes());
+                }
+            }
+            r.extend(transitions.iter().map(|(_, index)| index));
+            for ((offset, is_dst, _), index) in types.iter().zip(&indices) {
+                r.extend_from_slice(&offset.to_be_bytes());
+                r.push(*is_dst as u8);
+                r.push(*index);
+            }
+            r.extend_from_slice(&chars);
+            r
+        };
+        let mut r = block(4);
+        if version != 0 {
+            r.extend(block(8));
+            r.push(b'\n');
+            r.extend_from_slice(footer.as_bytes());
+            r.push(b'\n');
+        }
+        r
+    }
+
+    #[test]
+    fn version_1() {
+        let data = build(
+            0,
+            &[(1000, 1)],
+            &[(3600, false, "LMT"), (7200, true, "XDT")],
+            "",
+        );
+        let tz = TimeZone::from_tzif("Test/Zone", &data).unwrap();
+        assert_eq!(tz.id(), "Test/Zone");
+        assert_eq!(tz.get_second_offset(999), 3600);
+        assert_eq!(tz.get_second_offset(1000), 7200);
+        assert_eq!(tz.get_second_offset(i64::MAX / 2), 7200);
+    }
+
+    #[test]
+    fn footer_after_last_transition() {
+        let data = build(
+            b'2',
+            &[(-1_000_000_000, 1)],
+            &[(3208, false, "LMT"), (3600, false, "CET")],
+            "CET-1CEST,M3.5.0,M10.5.0/3",
+        );
+        let tz = TimeZone::from_tzif("Europe/Berlin", &data).unwrap();
+        assert_eq!(tz.get_second_offset(-1_000_000_001), 3208);
+        // 2023-07-01
+        assert_eq!(tz.get_second_offset(1_688_169_600), 7200);
+        // 2023-12-01
+        assert_eq!(tz.get_second_offset(1_701_388_800), 3600);
+        let date = IsoDate::new(2023, 3, 26).unwrap();
+        let gap = IsoTime {
+            hour: 2,
+            minute: 30,
+            ..IsoTime::default()
+        };
+        assert_eq!(tz.get_possible_seconds(date, gap), vec![]);
+        let date = IsoDate::new(2023, 10, 29).unwrap();
+        assert_eq!(tz.get_possible_seconds(date, gap).len(), 2);
+    }
+
+    #[test]
+    fn offset_info() {
+        let data = build(
+            b'2',
+            &[(0, 1), (1000, 2), (2000, 1), (3000, 0)],
+            &[
+                (3600, true, "XDT"),
+                (3600, false, "XST"),
+                (10800, true, "XDDT"),
+            ],
+            "",
+        );
+        let tz = TimeZone::from_tzif("Test/Zone", &data).unwrap();
+        let info = tz.get_offset_info(1500);
+        assert_eq!(info.abbreviation, "XDDT");
+        assert_eq!((info.standard_offset, info.dst_offset), (3600, 7200));
+        assert!(info.is_dst);
+        // Daylight saving types without a standard type before take the one after
+        let info = tz.get_offset_info(-1);
+        assert_eq!((info.standard_offset, info.dst_offset), (3600, 0));
+        assert!(info.is_dst);
+        assert!(!tz.get_offset_info(2500).is_dst);
+        let data = build(
+            b'2',
+            &[],
+            &[(3600, false, "CET")],
+            "CET-1CEST,M3.5.0,M10.5.0/3",
+        );
+        let tz = TimeZone::from_tzif("Europe/Berlin", &data).unwrap();
+        // 2023-07-01
+        let info = tz.get_offset_info(1_688_169_600);
+        assert_eq!(info.abbreviation, "CEST");
+        assert_eq!((info.standard_offset, info.dst_offset), (3600, 3600));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            TimeZone::from_tzif("x", b"TZ"),
+            Err(TzifError::NotTzif)
+        ));
+        let data = build(b'2', &[], &[(0, false, "UTC")], "UTC0");
+        assert!(TimeZone::from_tzif("UTC", &data).is_ok());
+        assert!(matches!(
+            TimeZone::from_tzif("UTC", &data[..data.len() - 3]),
+            Err(TzifError::Malformed)
+        ));
+        let data = build(b'3', &[], &[(0, false, "UTC")], "UTC");
+        assert!(matches!(
+            TimeZone::from_tzif("UTC", &data),
+            Err(TzifError::InvalidFooter(_))
+        ));
+        let data = build(b'5', &[], &[(0, false, "UTC")], "UTC0");
+        assert!(matches!(
+            TimeZone::from_tzif("UTC", &data),
+            Err(TzifError::UnsupportedVersion(b'5'))
+        ));
+        #[cfg(feature = "std")]
+        assert!(matches!(
+            TimeZone::from_zoneinfo("../../etc/passwd"),
+            Err(TzifError::InvalidName(_))
+        ));
+    }
+}
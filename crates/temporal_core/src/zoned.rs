@@ -1,9 +1,15 @@
-use std::str::FromStr;
+use alloc::{string::String, vec::Vec};
+use core::{
+    fmt::{Display, Write},
+    str::FromStr,
+};
 
 use crate::{
     duration::SignedDuration,
-    iso::{self, IsoDate, IsoOffset},
-    timezone::{TimeZone, TimeZoneProtocol},
+    error::{ParseError, ParseErrorKind},
+    format::{write_calendar, DisplayOffset, DisplayTimeZone, Precision, ToStringOptions},
+    iso::{self, Annotation, IsoDate, IsoOffset, IsoTime},
+    timezone::{format_offset_nanoseconds, OffsetInfo, TimeZone, TimeZoneProtocol},
     Calendar, CalendarProtocol, PlainDate,
 };
 
@@ -11,6 +17,7 @@ pub struct ZonedDateTime<T: TimeZoneProtocol = TimeZone, C: CalendarProtocol = C
     epoch: SignedDuration,
     calendar: C,
     timezone: T,
+    annotations: Vec<Annotation>,
 }
 
 impl<T: TimeZoneProtocol, C: CalendarProtocol> From<ZonedDateTime<T, C>> for PlainDate<C> {
@@ -20,9 +27,31 @@ impl<T: TimeZoneProtocol, C: CalendarProtocol> From<ZonedDateTime<T, C>> for Pla
 }
 
 impl<T: TimeZoneProtocol, C: CalendarProtocol> ZonedDateTime<T, C> {
+    /// Creates the date time at `epoch` since unix epoch, in the given time zone and calendar
+    pub fn new(epoch: SignedDuration, timezone: T, calendar: C) -> Self {
+        Self {
+            epoch,
+            calendar,
+            timezone,
+            annotations: Vec::new(),
+        }
+    }
+
+    /// Local date and time as time since unix epoch, honouring sub-second offsets
+    fn local(&self) -> SignedDuration {
+        self.epoch
+            .add_nanoseconds(self.timezone.get_nanosecond_offset(self.epoch))
+    }
+
     pub(crate) fn iso_date(&self) -> IsoDate {
-        let secs = self.epoch.as_secs() + self.timezone.get_second_offset(self.epoch.as_secs());
-        IsoDate::from_epoch_second(secs)
+        IsoDate::from_epoch_second(self.local().as_secs())
+    }
+
+    pub(crate) fn iso_time(&self) -> IsoTime {
+        let local = self.local();
+        let nanos =
+            local.as_secs().rem_euclid(86400) * 1_000_000_000 + i64::from(local.subsec_nanos());
+        IsoTime::from_nanosecond(nanos)
     }
 
     pub fn year(&self) -> i32 {
@@ -35,36 +64,76 @@ impl<T: TimeZoneProtocol, C: CalendarProtocol> ZonedDateTime<T, C> {
         self.calendar.day(self.iso_date())
     }
     pub fn hour(&self) -> u8 {
-        let secs = self.epoch.as_secs() + self.timezone.get_second_offset(self.epoch.as_secs());
-        secs.div_euclid(3600).rem_euclid(24) as u8
+        self.iso_time().hour
     }
     pub fn minute(&self) -> u8 {
-        let secs = self.epoch.as_secs() + self.timezone.get_second_offset(self.epoch.as_secs());
-        secs.div_euclid(60).rem_euclid(60) as u8
+        self.iso_time().minute
     }
-}
 
-#[non_exhaustive]
-#[derive(Debug)]
-pub enum ZonedDateTimeParseError {
-    MalformedIsoString,
-    UnknownTimeZone(String),
-    UnknownCalendar(String),
-    MissingTimezone,
-    NonUniqueTime,
-    WrongOffset,
-}
+    /// Annotations of the parsed string with keys unknown to this library, which are
+    /// written back by `Display`
+    pub fn annotations(&self) -> &[Annotation] {
+        &self.annotations
+    }
 
-impl FromStr for ZonedDateTime {
-    type Err = ZonedDateTimeParseError;
+    /// Abbreviation, offset and daylight saving status of the time zone at this instant
+    pub fn offset_info(&self) -> OffsetInfo {
+        self.timezone.get_offset_info(self.epoch.as_secs())
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        use ZonedDateTimeParseError::*;
-        let i = iso::parse(s).ok_or(MalformedIsoString)?;
-        let tz_name = i.timezone_name.ok_or(MissingTimezone)?;
-        let tz = TimeZone::from_str(&tz_name).map_err(|_| UnknownTimeZone(tz_name))?;
+    /// Writes the date time like `Display`, with the precision, rounding and annotations
+    /// of `options`. The instant is rounded before finding its local time, so the offset
+    /// is the one at the rounded instant.
+    pub fn to_string_with_options(&self, options: &ToStringOptions) -> String {
+        let mut s = String::new();
+        self.write(&mut s, options).unwrap();
+        s
+    }
+
+    fn write(&self, f: &mut impl Write, options: &ToStringOptions) -> core::fmt::Result {
+        let precision = Precision::new(options);
+        let epoch = self.epoch.round(precision.increment(), options.rounding_mode);
+        let offset = self.timezone.get_nanosecond_offset(epoch);
+        let local = epoch.add_nanoseconds(offset);
+        write!(f, "{}T", IsoDate::from_epoch_second(local.as_secs()))?;
+        precision.write_time(
+            f,
+            local.as_secs().rem_euclid(86400) * 1_000_000_000 + i64::from(local.subsec_nanos()),
+        )?;
+        if options.offset == DisplayOffset::Auto {
+            write!(f, "{}", format_offset_nanoseconds(offset))?;
+        }
+        match options.time_zone_name {
+            DisplayTimeZone::Auto => write!(f, "[{}]", self.timezone.id())?,
+            DisplayTimeZone::Never => {}
+            DisplayTimeZone::Critical => write!(f, "[!{}]", self.timezone.id())?,
+        }
+        write_calendar(f, self.calendar.id(), options.calendar_name)?;
+        for annotation in &self.annotations {
+            write!(f, "{}", annotation)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: TimeZoneProtocol> ZonedDateTime<T> {
+    /// Parses a date time like `FromStr`, but with the zone in brackets resolved by
+    /// `resolve`, which returns `None` for unknown ids. This is how strings written by
+    /// `Display` with a custom zone are parsed back.
+    pub fn parse_with(
+        s: &str,
+        resolve: impl FnOnce(&str) -> Option<T>,
+    ) -> Result<Self, ParseError> {
+        use ParseErrorKind::*;
+        let i = iso::parse(s)?;
+        let tz_name = i
+            .timezone_name
+            .ok_or(ParseError::new(MissingTimeZone, s.len()))?;
+        let tz = resolve(&tz_name)
+            .ok_or_else(|| ParseError::new(UnknownTimeZone(tz_name), i.timezone_name_at))?;
         let calendar = if let Some(c) = i.calendar {
-            c.parse().map_err(|_| UnknownCalendar(c))?
+            c.parse::<Calendar>()
+                .map_err(|e| e.shifted(i.calendar_at))?
         } else {
             Calendar::Iso8601
         };
@@ -73,24 +142,25 @@ impl FromStr for ZonedDateTime {
         } else {
             0
         };
-        let secs = i.date.to_epoch_second() + time_secs;
-        let real_secs = match i.timezone_offset {
+        let nanos = i.time.map_or(0, |x| x.to_nanosecond() % 1_000_000_000);
+        let local = SignedDuration::new(i.date.to_epoch_second() + time_secs, nanos as i32);
+        let epoch = match i.timezone_offset {
             Some(IsoOffset::Numeric(n)) => {
-                let offset_secs = n.to_seconds().into();
-                let x = secs - offset_secs;
-                let offset_tz = tz.get_second_offset(x);
-                if offset_tz != offset_secs {
-                    return Err(WrongOffset);
+                let offset = n.to_nanoseconds();
+                let x = local.add_nanoseconds(-offset);
+                if tz.get_nanosecond_offset(x) != offset {
+                    return Err(ParseError::new(WrongOffset, i.timezone_offset_at));
                 }
                 x
             }
             Some(IsoOffset::Z) => {
-                secs
+                local
             }
             None => {
-                let d = tz.get_possible_seconds(i.date, i.time.unwrap_or_default());
+                let d = tz.get_possible_epochs(i.date, i.time.unwrap_or_default());
                 if d.len() != 1 {
-                    return Err(NonUniqueTime);
+                    // Point where an offset would make the time unique
+                    return Err(ParseError::new(NonUniqueTime, i.timezone_offset_at));
                 }
                 d[0]
             }
@@ -98,7 +168,25 @@ impl FromStr for ZonedDateTime {
         Ok(Self {
             calendar,
             timezone: tz,
-            epoch: SignedDuration::from_secs(real_secs),
+            epoch,
+            annotations: i.annotations,
         })
     }
 }
+
+impl FromStr for ZonedDateTime {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(s, |id| TimeZone::from_str(id).ok())
+    }
+}
+
+/// Writes the date time like `2022-09-01T04:30:00+04:30[Asia/Tehran]`, with the calendar
+/// annotation if it's not ISO 8601 and the unknown annotations it was parsed with, which
+/// parses back to an equal date time
+impl<T: TimeZoneProtocol, C: CalendarProtocol> Display for ZonedDateTime<T, C> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.write(f, &ToStringOptions::default())
+    }
+}